@@ -0,0 +1,37 @@
+//! Measure the op code dispatch of `step`, run it with `cargo bench --bench dispatch`.
+//!
+//! Unlike `benches/step.rs`, the loop goes through a dozen different
+//! op codes and takes or skips a branch at each conditional one.
+
+extern crate reustmann;
+
+use std::io;
+use std::time::Instant;
+
+use reustmann::{Interpreter, Program, RunOutcome};
+
+const ARCH_LENGTH: usize = 4096;
+const ARCH_WIDTH: usize = 8;
+const STEPS: usize = 50_000_000;
+
+fn main() {
+    // every iteration pushes as many cells as it pops,
+    // the stack never reaches the code
+    let source = b"L0.D^Z;,p{;};pp]";
+    let program = Program::from_iter(source.iter().cloned());
+    let mut interpreter = Interpreter::new(ARCH_LENGTH, ARCH_WIDTH).unwrap();
+    interpreter.copy_program(&program).unwrap();
+    let code = interpreter.debug_infos().memory.0[..source.len()].to_vec();
+
+    let start = Instant::now();
+    let outcome = interpreter.run_with_limit(STEPS, &mut io::empty(), &mut io::sink());
+    let elapsed = start.elapsed();
+
+    match outcome {
+        RunOutcome::LimitReached(steps) => assert_eq!(steps, STEPS),
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+    assert_eq!(interpreter.debug_infos().memory.0[..source.len()], code[..]);
+    println!("{} steps in {:?}, {:.2} ns/step",
+        STEPS, elapsed, elapsed.as_nanos() as f64 / STEPS as f64);
+}
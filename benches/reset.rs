@@ -0,0 +1,35 @@
+//! Measure the speed of `reset` on a large machine, run it with `cargo bench --bench reset`.
+//!
+//! Every round dirties the memory with a short run before resetting it,
+//! as a harness running thousands of programs does.
+
+extern crate reustmann;
+
+use std::io;
+use std::time::Instant;
+
+use reustmann::{Interpreter, Program};
+
+const ARCH_LENGTH: usize = 1 << 20;
+const ARCH_WIDTH: usize = 8;
+const ROUNDS: usize = 2_000;
+const STEPS: usize = 64;
+
+fn main() {
+    // push ones forever, every step writes a new cell
+    let program = Program::from_iter(b"L0.]".iter().cloned());
+    let mut interpreter = Interpreter::new(ARCH_LENGTH, ARCH_WIDTH).unwrap();
+    interpreter.copy_program(&program).unwrap();
+    let image = interpreter.debug_infos().memory.0;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        interpreter.run_with_limit(STEPS, &mut io::empty(), &mut io::sink());
+        interpreter.reset();
+    }
+    let elapsed = start.elapsed();
+
+    assert_eq!(interpreter.debug_infos().memory.0, image);
+    println!("{} resets of {} cells in {:?}, {:.2} us/reset",
+        ROUNDS, ARCH_LENGTH, elapsed, elapsed.as_nanos() as f64 / ROUNDS as f64 / 1000.0);
+}
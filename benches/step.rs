@@ -0,0 +1,33 @@
+//! Measure the speed of the step loop, run it with `cargo bench --bench step`.
+//!
+//! The program loops forever without touching its own code,
+//! so every step goes through `current_opcode`.
+
+extern crate reustmann;
+
+use std::io;
+use std::time::Instant;
+
+use reustmann::{Interpreter, Program, RunOutcome};
+
+const ARCH_LENGTH: usize = 4096;
+const ARCH_WIDTH: usize = 8;
+const STEPS: usize = 50_000_000;
+
+fn main() {
+    // push a zero, increment it forever
+    let program = Program::from_iter(b"L0.p]".iter().cloned());
+    let mut interpreter = Interpreter::new(ARCH_LENGTH, ARCH_WIDTH).unwrap();
+    interpreter.copy_program(&program).unwrap();
+
+    let start = Instant::now();
+    let outcome = interpreter.run_with_limit(STEPS, &mut io::empty(), &mut io::sink());
+    let elapsed = start.elapsed();
+
+    match outcome {
+        RunOutcome::LimitReached(steps) => assert_eq!(steps, STEPS),
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    }
+    println!("{} steps in {:?}, {:.2} ns/step",
+        STEPS, elapsed, elapsed.as_nanos() as f64 / STEPS as f64);
+}
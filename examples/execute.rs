@@ -17,7 +17,7 @@ fn main() {
     let program = Program::from_iter(instructions.into_bytes());
 
     let mut interpreter = Interpreter::new(ARCH_LENGTH, ARCH_WIDTH).unwrap();
-    interpreter.copy_program(&program);
+    interpreter.copy_program(&program).unwrap();
 
     let mut input = io::stdin();
     let mut output = Vec::new();
@@ -0,0 +1,13 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate reustmann;
+
+const MAX_STEPS: usize = 1000;
+
+// the first byte gives the length of the program, the rest is the input
+fuzz_target!(|data: &[u8]| {
+    if let Some((&len, data)) = data.split_first() {
+        let (program, input) = data.split_at((len as usize).min(data.len()));
+        reustmann::fuzz_one(program, input, MAX_STEPS);
+    }
+});
@@ -0,0 +1,71 @@
+//! Static analysis helpers working on programs before they are executed.
+
+use std::collections::HashSet;
+
+use crate::instruction::op_codes::*;
+use crate::program::Program;
+
+/// Find the cells that can be executed when the program is run from `pc = 0`,
+/// on a machine of exactly the program length.
+///
+/// The sweep follows fall-through and the branch targets that can be
+/// determined statically, it is conservative: a `POPPC` jumps to an address
+/// only known at runtime, so when one is reachable every cell is reported.
+/// Self-modifying code is not taken into account.
+pub fn reachable_cells(program: &Program) -> HashSet<usize> {
+    let memory = program.op_codes();
+    let len = memory.len();
+    let mut reachable = HashSet::new();
+    let mut to_visit = if len == 0 { Vec::new() } else { vec![0] };
+
+    while let Some(addr) = to_visit.pop() {
+        if !reachable.insert(addr) { continue }
+        let next = (addr + 1) % len;
+        match memory[addr] {
+            HALT => (),
+            RESET => to_visit.push(0),
+            POPPC => return (0..len).collect(),
+            BZ | BNZ | BEQ | BGT | BLT | BGE => {
+                to_visit.push(next);
+                to_visit.push((addr + 2) % len);
+            },
+            ENDL => {
+                let target = memory[..addr].iter().rposition(|&op| op == LOOP);
+                to_visit.push(target.map_or(next, |i| (i + 1) % len));
+            },
+            BRAN => {
+                let target = memory[addr + 1..].iter().position(|&op| op == TARGET);
+                to_visit.push(target.map_or(next, |i| (addr + 1 + i + 1) % len));
+            },
+            BRAP => {
+                let target = memory[..addr].iter().rposition(|&op| op == TARGET);
+                to_visit.push(target.map_or(next, |i| (i + 1) % len));
+            },
+            op @ SKIP1..=SKIP9 => to_visit.push((addr + (op - SKIP1) as usize + 2) % len),
+            _ => to_visit.push(next),
+        }
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reachable(source: &[u8]) -> Vec<usize> {
+        let mut cells: Vec<_> = reachable_cells(&Program::from_iter(source.iter().cloned())).into_iter().collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn jumped_over_cells_are_unreachable() {
+        // the `BRAN` jumps after the `TARGET`, the `HALT`s between are dead code
+        assert_eq!(reachable(b"BHHT.H"), [0, 4, 5]);
+        // both sides of a conditional branch, nothing after the `HALT`
+        assert_eq!(reachable(b"Z.H..H"), [0, 1, 2]);
+        assert_eq!(reachable(b"0ZH.H."), [0, 1, 2, 3, 4]);
+        // a `POPPC` can jump anywhere
+        assert_eq!(reachable(b"cHHH"), [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,202 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::instruction::{Instruction, Mnemonic, mnemonics};
+
+/// Errors returned by `Program::from_source`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleError {
+    /// The token is not a mnemonic, `line` starts at `1`.
+    UnknownMnemonic {
+        line: usize,
+        token: String
+    },
+    /// An `@name` refers to a label that is never defined.
+    UndefinedLabel {
+        name: String
+    },
+    /// The label is defined twice.
+    DuplicateLabel {
+        name: String
+    },
+    /// The label is at an address that doesn't fit in a cell.
+    LabelOutOfRange {
+        name: String,
+        address: usize
+    },
+}
+
+impl AssembleError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            AssembleError::UnknownMnemonic { .. } => "Unknown mnemonic",
+            AssembleError::UndefinedLabel { .. } => "Undefined label",
+            AssembleError::DuplicateLabel { .. } => "Duplicate label",
+            AssembleError::LabelOutOfRange { .. } => "Label address out of the cell range",
+        }
+    }
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AssembleError::UnknownMnemonic { line, ref token } => {
+                write!(f, "{} {:?} at line {}", self.description(), token, line)
+            },
+            AssembleError::UndefinedLabel { ref name } | AssembleError::DuplicateLabel { ref name } => {
+                write!(f, "{} {:?}", self.description(), name)
+            },
+            AssembleError::LabelOutOfRange { ref name, address } => {
+                write!(f, "{} {:?} ({} > {})", self.description(), name, address, u8::MAX)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AssembleError {}
+
+/// The number of bits of an address pushed by an `@label`.
+const ADDRESS_BITS: usize = 8;
+
+/// The number of cells of the code pushing an address.
+const PUSH_ADDRESS_CELLS: usize = 1 + 2 * ADDRESS_BITS;
+
+/// The source line of each cell of an assembled program, lines start at `1`.
+///
+/// The code pushing the address of an `@label` maps to the line of the reference.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SourceMap(pub Vec<usize>);
+
+impl SourceMap {
+    /// The source line of the cell at `address`, if it is part of the program.
+    pub fn line(&self, address: usize) -> Option<usize> {
+        self.0.get(address).cloned()
+    }
+}
+
+/// A cell or the code pushing the address of a label, with its source line.
+enum Item<'a> {
+    Mnemonic(u8, usize),
+    Address(&'a str, usize),
+}
+
+/// Append the code pushing `address` on the stack, always `PUSH_ADDRESS_CELLS` long
+/// so that the addresses of the labels are known before the references are resolved.
+///
+/// It pushes `0` then shifts it left and increments it for each bit of the address,
+/// from the most significant one, a `Nop` replaces the `Inc` of a clear bit.
+fn push_address(mnemos: &mut Vec<u8>, address: u8) {
+    mnemos.push(mnemonics::PUSH0 as u8);
+    for bit in (0..ADDRESS_BITS).rev() {
+        mnemos.push(mnemonics::SHL as u8);
+        let set = address & (1 << bit) != 0;
+        mnemos.push(if set { mnemonics::INC } else { mnemonics::NOP } as u8);
+    }
+}
+
+/// Translate whitespace separated short or long mnemonics to the short mnemonics of a source,
+/// a `;` starts a comment up to the end of the line, `Nop` can't be written `;`.
+///
+/// A `name:` token defines a label at the address of the next cell,
+/// an `@name` token is replaced by the code pushing the address of the label,
+/// labels can be referenced before their definition.
+pub(crate) fn assemble(text: &str) -> Result<(Vec<u8>, SourceMap), AssembleError> {
+    let mut items = Vec::new();
+    let mut labels = BTreeMap::new();
+    let mut length = 0;
+    for (index, line) in text.lines().enumerate() {
+        let code = line.split(';').next().unwrap_or("");
+        for token in code.split_whitespace() {
+            if let Some(name) = token.strip_suffix(':').filter(|name| !name.is_empty()) {
+                if labels.insert(name, length).is_some() {
+                    return Err(AssembleError::DuplicateLabel { name: name.to_string() })
+                }
+            }
+            else if let Some(name) = token.strip_prefix('@').filter(|name| !name.is_empty()) {
+                items.push(Item::Address(name, index + 1));
+                length += PUSH_ADDRESS_CELLS;
+            }
+            else {
+                match token.parse::<Instruction>() {
+                    Ok(instruction) => items.push(Item::Mnemonic(Mnemonic::from(instruction) as u8, index + 1)),
+                    Err(_) => return Err(AssembleError::UnknownMnemonic { line: index + 1, token: token.to_string() }),
+                }
+                length += 1;
+            }
+        }
+    }
+
+    let mut mnemos = Vec::with_capacity(length);
+    let mut lines = Vec::with_capacity(length);
+    for item in items {
+        match item {
+            Item::Mnemonic(mnemo, line) => {
+                mnemos.push(mnemo);
+                lines.push(line);
+            },
+            Item::Address(name, line) => {
+                let address = *labels.get(name).ok_or_else(|| AssembleError::UndefinedLabel { name: name.to_string() })?;
+                if address > u8::MAX as usize {
+                    return Err(AssembleError::LabelOutOfRange { name: name.to_string(), address })
+                }
+                push_address(&mut mnemos, address as u8);
+                lines.resize(mnemos.len(), line);
+            },
+        }
+    }
+    Ok((mnemos, SourceMap(lines)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_and_long_mnemonics() {
+        assert_eq!(assemble("LOOP I z halt ; comment\nO ]").unwrap().0, b"LIzHO]");
+        assert_eq!(assemble("Nop ;").unwrap().0, b";");
+    }
+
+    #[test]
+    fn unknown_mnemonic_line() {
+        let err = assemble("Push0\nOut Foo\n").unwrap_err();
+        assert_eq!(err, AssembleError::UnknownMnemonic { line: 2, token: "Foo".to_string() });
+    }
+
+    #[test]
+    fn source_map_lines() {
+        let (mnemos, map) = assemble("start:\n  Push0 ; first\n\n  @start PopPc\n").unwrap();
+        assert_eq!(mnemos.len(), 2 + PUSH_ADDRESS_CELLS);
+        assert_eq!(map.line(0), Some(2));
+        assert!((1..=PUSH_ADDRESS_CELLS).all(|addr| map.line(addr) == Some(4)));
+        assert_eq!(map.line(PUSH_ADDRESS_CELLS + 1), Some(4));
+        assert_eq!(map.line(mnemos.len()), None);
+    }
+
+    #[test]
+    fn forward_label_reference() {
+        let (mnemos, _) = assemble("@end PopPc\nOut\nend: Halt").unwrap();
+        let end = PUSH_ADDRESS_CELLS + 2;
+        let mut expected = Vec::new();
+        push_address(&mut expected, end as u8);
+        expected.extend_from_slice(b"cOH");
+        assert_eq!(mnemos, expected);
+
+        // the `Out` is jumped over
+        let mut interpreter = crate::Interpreter::new(32, 8).unwrap();
+        interpreter.copy_program_bytes(&mnemos).unwrap();
+        let mut output = Vec::new();
+        interpreter.run(&mut &b""[..], &mut output);
+        assert_eq!(interpreter.debug_view().pc, end);
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn undefined_and_duplicate_labels() {
+        assert_eq!(assemble("@nowhere PopPc").unwrap_err(), AssembleError::UndefinedLabel { name: "nowhere".to_string() });
+        assert_eq!(assemble("a: Halt\na: Out").unwrap_err(), AssembleError::DuplicateLabel { name: "a".to_string() });
+    }
+}
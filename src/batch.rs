@@ -0,0 +1,51 @@
+use std::io::{empty, sink};
+
+use rayon::prelude::*;
+
+use crate::interpreter::{Interpreter, RunOutcome};
+use crate::program::Program;
+
+/// Run each program on a machine of the length of the longest program (at least one word)
+/// and `arch_width`, for `max_steps` instructions or until a `HALT` or a failed step.
+///
+/// The runs are independent and spread over the rayon thread pool,
+/// the input is empty and the output is discarded.
+/// The outcomes are in the order of `programs`, a program that can't be loaded,
+/// for example with an invalid `arch_width`, is `Rejected`.
+pub fn run_batch(programs: &[Program], arch_width: usize, max_steps: usize) -> Vec<RunOutcome> {
+    // every program runs with the same arch length, the stack wraps in it
+    let arch_length = programs.iter().map(|program| program.memory().len()).max().unwrap_or(0).max(1);
+    // each worker reuses its interpreter for the programs it runs
+    programs.par_iter().map_init(|| Interpreter::new(arch_length, arch_width), |interpreter, program| {
+        let interpreter = match *interpreter {
+            Ok(ref mut interpreter) => interpreter,
+            Err(err) => return RunOutcome::Rejected(err),
+        };
+        match interpreter.reset_with_program(program) {
+            Ok(()) => interpreter.run_with_limit(max_steps, &mut empty(), &mut sink()),
+            Err(err) => RunOutcome::Rejected(err.description()),
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_programs_run_with_the_longest_length() {
+        // on a memory of 2 cells the push of `0H` would overwrite the `HALT`
+        let programs = [Program::from_iter(b"0H".iter().cloned()), Program::from_iter(vec![b'H'; 16])];
+        let outcomes = run_batch(&programs, 8, 100);
+        assert!(matches!(outcomes[0], RunOutcome::Halted(2)), "{:?}", outcomes[0]);
+        assert!(matches!(outcomes[1], RunOutcome::Halted(1)), "{:?}", outcomes[1]);
+    }
+
+    #[test]
+    fn invalid_width_rejects_every_program() {
+        let programs = [Program::from_iter(b"H".iter().cloned()), Program::from_iter(b"0H".iter().cloned())];
+        let outcomes = run_batch(&programs, 64, 100);
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.iter().all(|outcome| matches!(outcome, RunOutcome::Rejected(_))));
+    }
+}
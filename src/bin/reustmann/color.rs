@@ -0,0 +1,41 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, Clone, Copy)]
+pub enum ColorMode {
+    Always,
+    Auto,
+    Never,
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(ColorMode::Always),
+            "auto" => Ok(ColorMode::Auto),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(format!("invalid color mode {:?}, expected always, auto or never", s)),
+        }
+    }
+}
+
+/// In `Auto` mode colors are disabled when stdout is not a terminal
+/// or when the `NO_COLOR` environment variable is set.
+pub fn set_color_mode(mode: ColorMode) {
+    let enabled = match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal(),
+    };
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
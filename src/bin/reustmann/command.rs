@@ -1,6 +1,113 @@
-use std::borrow::Cow;
+use std::fmt;
 use std::str::{self, FromStr};
 
+use reustmann::DumpFormat;
+use reustmann::instruction::Instruction;
+
+use display::{AddressMode, NumberBase};
+
+/// Parse a decimal or a `0x` prefixed hexadecimal number.
+fn parse_value(s: &str) -> Result<usize, String> {
+    let result = match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse::<usize>(),
+    };
+    result.map_err(|e| format!("invalid value {:?}: {}", s, e))
+}
+
+/// The names accepted by the parser, used to suggest a command on typos.
+const COMMAND_NAMES: &[&str] = &[
+    "unset_interpreter", "interpreter", "infos_interpreter", "infos", "copy", "edit", "asm", "assemble",
+    "diff", "check", "checksum", "dumpmem", "history", "output", "halts", "trace", "set", "setreg", "reset", "step", "next",
+    "run", "until", "until_output", "break_on", "break_op", "repeat", "exit", "quit",
+];
+
+/// The Levenshtein distance between two words.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The known command closest to `name`, if it is close enough to be a typo.
+fn suggest_command(name: &str) -> Option<&'static str> {
+    COMMAND_NAMES.iter()
+                 .map(|&command| (edit_distance(name, command), command))
+                 .filter(|&(distance, _)| distance <= 2 && distance < name.len())
+                 .min_by_key(|&(distance, _)| distance)
+                 .map(|(_, command)| command)
+}
+
+#[derive(Debug, Clone)]
+pub enum CommandParseError {
+    UnknownCommand(String),
+    BadArgument {
+        command: String,
+        detail: String
+    },
+    MissingArgument(&'static str),
+}
+
+impl fmt::Display for CommandParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CommandParseError::UnknownCommand(ref command) => {
+                write!(f, "unknown command {:?}", command)?;
+                match suggest_command(command) {
+                    Some(suggestion) => write!(f, "; did you mean {:?}?", suggestion),
+                    None => Ok(()),
+                }
+            },
+            CommandParseError::BadArgument { ref command, ref detail } => write!(f, "{}: {}", command, detail),
+            CommandParseError::MissingArgument(argument) => write!(f, "missing {}", argument),
+        }
+    }
+}
+
+fn bad_argument<D: fmt::Display>(command: &str, detail: D) -> CommandParseError {
+    CommandParseError::BadArgument { command: command.to_string(), detail: detail.to_string() }
+}
+
+/// Parse a required argument.
+fn parse_arg<T>(command: &str, arg: Option<&str>, name: &'static str) -> Result<T, CommandParseError>
+    where T: FromStr, T::Err: fmt::Display {
+
+    let arg = arg.ok_or(CommandParseError::MissingArgument(name))?;
+    arg.parse().map_err(|e| bad_argument(command, e))
+}
+
+/// Parse an optional argument.
+fn parse_opt_arg<T>(command: &str, arg: Option<&str>) -> Result<Option<T>, CommandParseError>
+    where T: FromStr, T::Err: fmt::Display {
+
+    arg.map(|arg| arg.parse().map_err(|e| bad_argument(command, e))).transpose()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Register {
+    Pc,
+    Sp,
+    Nz,
+}
+
+#[derive(Debug, Clone)]
+pub enum Setting {
+    Lines(usize),
+    Addr(AddressMode),
+    Base(NumberBase),
+    Echo(bool),
+    TailSize(usize),
+}
+
 #[derive(Debug, Clone)]
 pub enum Command {
     UnsetInterpreter,
@@ -9,58 +116,195 @@ pub enum Command {
         arch_width: usize
     },
     InfosInterpreter,
-    Infos,
+    Infos { json: bool },
     Copy(String, bool),
+    Edit,
+    Asm(String),
+    Assemble(String),
+    Diff(String, String),
+    Check(String),
+    Checksum(String, Option<u32>),
+    DumpMemory(String, DumpFormat),
+    History,
+    OutputTail(Option<usize>),
+    Halts,
+    Trace(bool),
+    Set(Setting),
+    SetRegister(Register, usize),
     Reset,
     Step(usize),
+    Run(Option<usize>),
+    UntilOutput(Option<usize>),
+    Until {
+        addr: usize,
+        max_steps: Option<usize>
+    },
+    BreakOnNz(Option<usize>),
+    BreakOp(Instruction, Option<usize>),
     Repeat,
     Exit,
 }
 
 impl FromStr for Command {
-    type Err = Cow<'static, str>;
+    type Err = CommandParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut iter = s.split_whitespace();
-        match iter.next() {
-            Some("unset_interpreter") => Ok(Command::UnsetInterpreter),
-            Some("interpreter") => {
-                let arch_length = match iter.next().map(|s| s.parse::<usize>()) {
-                    Some(Ok(value)) => value,
-                    Some(Err(e)) => return Err(e.to_string().into()),
-                    None => return Err("missing arch length".into()),
-                };
-
-                let arch_width = match iter.next().map(|s| s.parse::<usize>()) {
-                    Some(Ok(value)) => value,
-                    Some(Err(e)) => return Err(e.to_string().into()),
-                    None => return Err("missing arch width".into()),
-                };
-
+        let command = match iter.next() {
+            Some(command) => command,
+            None => return Ok(Command::Repeat),
+        };
+        match command {
+            "unset_interpreter" => Ok(Command::UnsetInterpreter),
+            "interpreter" => {
+                let arch_length = parse_arg(command, iter.next(), "arch length")?;
+                let arch_width = parse_arg(command, iter.next(), "arch width")?;
                 Ok(Command::SetInterpreter { arch_length, arch_width })
             },
-            Some("infos_interpreter") => Ok(Command::InfosInterpreter),
-            Some("infos") => Ok(Command::Infos),
-            Some("copy") => {
-                let file_name = iter.next().ok_or("missing file name")?;
-                let skip_newline = match iter.next().map(|s| s.parse::<bool>()) {
-                    Some(Ok(value)) => value,
-                    Some(Err(e)) => return Err(e.to_string().into()),
-                    None => true,
-                };
+            "infos_interpreter" => Ok(Command::InfosInterpreter),
+            "infos" => {
+                match iter.next() {
+                    Some("--json") => Ok(Command::Infos { json: true }),
+                    Some(s) => Err(bad_argument(command, format!("invalid option {:?}", s))),
+                    None => Ok(Command::Infos { json: false }),
+                }
+            },
+            "copy" => {
+                let file_name = iter.next().ok_or(CommandParseError::MissingArgument("file name"))?;
+                let skip_newline = parse_opt_arg(command, iter.next())?.unwrap_or(true);
                 Ok(Command::Copy(file_name.to_string(), skip_newline))
             },
-            Some("reset") => Ok(Command::Reset),
-            Some("step") | Some("s") | Some("next") | Some("n") => {
-                let count = match iter.next() {
-                    Some(s) => s.parse::<usize>().map_err(|e| e.to_string())?,
-                    None => 1,
+            "edit" => Ok(Command::Edit),
+            "asm" => {
+                let source: Vec<_> = iter.collect();
+                if source.is_empty() { return Err(CommandParseError::MissingArgument("source")) }
+                Ok(Command::Asm(source.join(" ")))
+            },
+            "assemble" => {
+                let file_name = iter.next().ok_or(CommandParseError::MissingArgument("file name"))?;
+                Ok(Command::Assemble(file_name.to_string()))
+            },
+            "diff" => {
+                let first = iter.next().ok_or(CommandParseError::MissingArgument("first file name"))?;
+                let second = iter.next().ok_or(CommandParseError::MissingArgument("second file name"))?;
+                Ok(Command::Diff(first.to_string(), second.to_string()))
+            },
+            "check" => {
+                let file_name = iter.next().ok_or(CommandParseError::MissingArgument("file name"))?;
+                Ok(Command::Check(file_name.to_string()))
+            },
+            "checksum" => {
+                let file_name = iter.next().ok_or(CommandParseError::MissingArgument("file name"))?;
+                let expected = match iter.next() {
+                    Some(value) => {
+                        let value = parse_value(value).map_err(|e| bad_argument(command, e))?;
+                        if value > u32::MAX as usize { return Err(bad_argument(command, "checksum must fit in 32 bits")) }
+                        Some(value as u32)
+                    },
+                    None => None,
+                };
+                Ok(Command::Checksum(file_name.to_string(), expected))
+            },
+            "dumpmem" => {
+                let file_name = iter.next().ok_or(CommandParseError::MissingArgument("file name"))?;
+                let format = match iter.next() {
+                    Some("raw") | None => DumpFormat::Raw,
+                    Some("hex") => DumpFormat::Hex,
+                    Some("hexascii") => DumpFormat::HexAscii,
+                    Some(s) => return Err(bad_argument(command, format!("invalid dump format {:?}, expected raw, hex or hexascii", s))),
                 };
+                Ok(Command::DumpMemory(file_name.to_string(), format))
+            },
+            "history" => Ok(Command::History),
+            "output" => {
+                match iter.next() {
+                    Some("tail") => Ok(Command::OutputTail(parse_opt_arg(command, iter.next())?)),
+                    Some(s) => Err(bad_argument(command, format!("invalid output command {:?}, expected tail", s))),
+                    None => Err(CommandParseError::MissingArgument("output command")),
+                }
+            },
+            "halts" => Ok(Command::Halts),
+            "trace" => {
+                match iter.next() {
+                    Some("on") => Ok(Command::Trace(true)),
+                    Some("off") => Ok(Command::Trace(false)),
+                    Some(s) => Err(bad_argument(command, format!("invalid trace mode {:?}, expected on or off", s))),
+                    None => Err(CommandParseError::MissingArgument("trace mode")),
+                }
+            },
+            "set" => {
+                match iter.next() {
+                    Some("lines") => {
+                        let lines = parse_arg(command, iter.next(), "number of lines")?;
+                        Ok(Command::Set(Setting::Lines(lines)))
+                    },
+                    Some("addr") => {
+                        let mode: AddressMode = parse_arg(command, iter.next(), "address mode")?;
+                        Ok(Command::Set(Setting::Addr(mode)))
+                    },
+                    Some("base") => {
+                        let base: NumberBase = parse_arg(command, iter.next(), "number base")?;
+                        Ok(Command::Set(Setting::Base(base)))
+                    },
+                    Some("tail") => {
+                        let size = parse_arg(command, iter.next(), "number of bytes")?;
+                        Ok(Command::Set(Setting::TailSize(size)))
+                    },
+                    Some("echo") => {
+                        match iter.next() {
+                            Some("on") => Ok(Command::Set(Setting::Echo(true))),
+                            Some("off") => Ok(Command::Set(Setting::Echo(false))),
+                            Some(s) => Err(bad_argument(command, format!("invalid echo mode {:?}, expected on or off", s))),
+                            None => Err(CommandParseError::MissingArgument("echo mode")),
+                        }
+                    },
+                    Some(s) => Err(bad_argument(command, format!("invalid setting {:?}", s))),
+                    None => Err(CommandParseError::MissingArgument("setting")),
+                }
+            },
+            "setreg" => {
+                let register = match iter.next() {
+                    Some("pc") => Register::Pc,
+                    Some("sp") => Register::Sp,
+                    Some("nz") => Register::Nz,
+                    Some(s) => return Err(bad_argument(command, format!("invalid register {:?}, expected pc, sp or nz", s))),
+                    None => return Err(CommandParseError::MissingArgument("register")),
+                };
+                let value = iter.next().ok_or(CommandParseError::MissingArgument("register value"))?;
+                let value = parse_value(value).map_err(|e| bad_argument(command, e))?;
+                if let Register::Nz = register {
+                    if value > 1 { return Err(bad_argument(command, "nz value must be 0 or 1")) }
+                }
+                Ok(Command::SetRegister(register, value))
+            },
+            "reset" => Ok(Command::Reset),
+            "step" | "s" | "next" | "n" => {
+                let count = parse_opt_arg(command, iter.next())?.unwrap_or(1);
                 Ok(Command::Step(count))
             },
-            Some("repeat") | None => Ok(Command::Repeat),
-            Some("exit") | Some("quit") | Some("q") => Ok(Command::Exit),
-            Some(command) => Err(format!("invalid command {:?}", command).into()),
+            "run" | "r" => Ok(Command::Run(parse_opt_arg(command, iter.next())?)),
+            "until" => {
+                let addr = iter.next().ok_or(CommandParseError::MissingArgument("address"))?;
+                let addr = parse_value(addr).map_err(|e| bad_argument(command, e))?;
+                let max_steps = parse_opt_arg(command, iter.next())?;
+                Ok(Command::Until { addr, max_steps })
+            },
+            "until_output" => Ok(Command::UntilOutput(parse_opt_arg(command, iter.next())?)),
+            "break_on" => {
+                match iter.next() {
+                    Some("nz") => (),
+                    Some(cond) => return Err(bad_argument(command, format!("invalid break condition {:?}", cond))),
+                    None => return Err(CommandParseError::MissingArgument("break condition")),
+                }
+                Ok(Command::BreakOnNz(parse_opt_arg(command, iter.next())?))
+            },
+            "break_op" => {
+                let instruction: Instruction = parse_arg(command, iter.next(), "mnemonic")?;
+                Ok(Command::BreakOp(instruction, parse_opt_arg(command, iter.next())?))
+            },
+            "repeat" => Ok(Command::Repeat),
+            "exit" | "quit" | "q" => Ok(Command::Exit),
+            command => Err(CommandParseError::UnknownCommand(command.to_string())),
         }
     }
 }
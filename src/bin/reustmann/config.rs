@@ -0,0 +1,100 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use color::ColorMode;
+use display::{AddressMode, NumberBase};
+
+/// The startup defaults read from `~/.rmdbrc`, the command line flags override them.
+///
+/// The file holds one `key = value` per line, values can be quoted
+/// and lines starting with `#` are comments:
+///
+/// ```text
+/// arch_length = 64
+/// arch_width = 8
+/// lines = 30
+/// color = "never"
+/// addr = "absolute"
+/// base = "dec"
+/// ```
+///
+/// It is the flat subset of TOML these keys need, parsed by hand to not pull
+/// a TOML parser in the binary: tables, arrays and escapes are not supported.
+#[derive(Debug, Default)]
+pub struct Config {
+    pub arch_length: Option<usize>,
+    pub arch_width: Option<usize>,
+    pub lines: Option<usize>,
+    pub color: Option<ColorMode>,
+    pub addr: Option<AddressMode>,
+    pub base: Option<NumberBase>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse { line: usize, detail: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref err) => write!(f, "~/.rmdbrc: {}", err),
+            ConfigError::Parse { line, ref detail } => write!(f, "~/.rmdbrc:{}: {}", line, detail),
+        }
+    }
+}
+
+fn parse_value<T>(value: &str) -> Result<Option<T>, String>
+    where T: FromStr, T::Err: fmt::Display {
+
+    value.parse::<T>().map(Some).map_err(|e| e.to_string())
+}
+
+impl FromStr for Config {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = Config::default();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let parse_error = |detail| ConfigError::Parse { line: index + 1, detail };
+            let (key, value) = line.split_once('=').ok_or_else(|| parse_error("expected key = value".to_string()))?;
+            let value = value.trim().trim_matches('"');
+            let result = match key.trim() {
+                "arch_length" => parse_value(value).map(|v| config.arch_length = v),
+                "arch_width" => parse_value(value).map(|v| config.arch_width = v),
+                "lines" => parse_value(value).map(|v| config.lines = v),
+                "color" => parse_value(value).map(|v| config.color = v),
+                "addr" => parse_value(value).map(|v| config.addr = v),
+                "base" => parse_value(value).map(|v| config.base = v),
+                key => Err(format!("unknown key {:?}", key)),
+            };
+            result.map_err(parse_error)?;
+        }
+        Ok(config)
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".rmdbrc"))
+}
+
+/// Read `~/.rmdbrc`, a missing file gives the default config.
+pub fn load_config() -> Result<Config, ConfigError> {
+    let path = match config_path() {
+        Some(path) => path,
+        None => return Ok(Config::default()),
+    };
+    match fs::read_to_string(path) {
+        Ok(content) => content.parse(),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(ConfigError::Io(err)),
+    }
+}
@@ -1,24 +1,60 @@
+use std::cmp::min;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::fs;
 use std::io::{self, Read, Write};
+use std::mem;
 use std::path::Path;
+use std::process;
 
-use reustmann::{Interpreter, DebugInfos, Program, Statement};
-use reustmann::instruction::op_codes;
+use reustmann::{Interpreter, DebugInfos, DumpFormat, Program, ReustmannError, SourceMap, Statement};
+use reustmann::instruction::{op_codes, Instruction};
 
 use debugger_error::DebuggerError;
-use command::Command;
-use display;
+use command::{Command, Register, Setting};
+use display::{self, AddressMode, NumberBase};
+use echo_input::EchoInput;
+use output_tail::{OutputTail, TailOutput, DEFAULT_TAIL_SIZE};
 use sink_debug::DebugWrite;
 
-const DEFAULT_ARCH_WIDTH: usize = 8;
+pub const DEFAULT_ARCH_WIDTH: usize = 8;
+const DEFAULT_STEP_LIMIT: usize = 10_000;
 
+/// The number of changed cells listed after a `run`.
+const CHANGED_CELLS_LIMIT: usize = 32;
+
+/// Lines used around the memory view: the output, registers,
+/// last statement and status lines plus the prompt.
+const RESERVED_LINES: usize = 5;
+
+/// Read a program file, without its final newline if `ignore_nl` is `true`.
 fn create_program_from_file<P: AsRef<Path>>(path: &P, ignore_nl: bool) -> io::Result<Program> {
-    let mut instructions = fs::read(path)?;
-    if ignore_nl && instructions.last() == Some(&b'\n') {
-        instructions.pop();
+    fs::read(path).map(|mut bytes| {
+        // the other `\n`s are `SpTgt` op codes, dropping them would shift the addresses
+        if ignore_nl && bytes.last() == Some(&b'\n') {
+            bytes.pop();
+        }
+        Program::from_iter(bytes)
+    })
+}
+
+/// Open `path` in `$EDITOR` and wait for it to exit.
+fn edit_file(path: &str) -> Result<(), String> {
+    let editor = std::env::var("EDITOR").map_err(|_| "$EDITOR is not set.".to_string())?;
+    // the editor can come with its own arguments, like `code --wait`
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or_else(|| "$EDITOR is empty.".to_string())?;
+    let status = process::Command::new(program).args(words).arg(path).status()
+                                  .map_err(|e| format!("cannot run {:?}: {}", editor, e))?;
+    if status.success() { Ok(()) }
+    else { Err(format!("{:?} exited with {}", editor, status)) }
+}
+
+fn push_history(history: &mut VecDeque<Statement>, size: usize, statement: Statement) {
+    if history.len() == size {
+        history.pop_front();
     }
-    Ok(Program::from_iter(instructions))
+    history.push_back(statement);
 }
 
 fn display_debugger_error(dbg_err: &DebuggerError) {
@@ -28,6 +64,8 @@ fn display_debugger_error(dbg_err: &DebuggerError) {
             printlnc!(yellow: "{}", "`interpreter [arch_length] [arch_width]` to create one")
         },
         DebuggerError::InterpreterCreation(_) => printlnc!(red: "{}", dbg_err.description()),
+        DebuggerError::Reustmann(err) => printlnc!(red: "{}", err),
+        DebuggerError::Io(ref err) => printlnc!(red: "{}", err),
     }
 }
 
@@ -35,9 +73,21 @@ pub struct Debugger {
     interpreter: Option<Interpreter>,
     number_of_cycles: usize,
     program_name: Option<String>,
+    /// The map and the text of the assembled source, to show the line of `pc`.
+    source: Option<(SourceMap, String)>,
+    ignore_nl: bool,
     statement: Option<Statement>,
+    history: VecDeque<Statement>,
+    trace: bool,
     pc_lines: usize,
-    sp_lines: usize
+    sp_lines: usize,
+    height: Option<usize>,
+    address_mode: AddressMode,
+    number_base: NumberBase,
+    default_arch_width: Option<usize>,
+    quiet: bool,
+    echo: bool,
+    output_tail: OutputTail
 }
 
 impl Default for Debugger {
@@ -52,38 +102,96 @@ impl Debugger {
             interpreter: None,
             number_of_cycles: 0,
             program_name: None,
+            source: None,
+            ignore_nl: true,
             statement: None,
+            history: VecDeque::new(),
+            trace: false,
             pc_lines: 10, // FIXME pc_lines need to be always >= sp_lines
-            sp_lines: 5
+            sp_lines: 5,
+            height: None,
+            address_mode: AddressMode::Both,
+            number_base: NumberBase::Hex,
+            default_arch_width: None,
+            quiet: false,
+            echo: false,
+            output_tail: OutputTail::new(DEFAULT_TAIL_SIZE)
         }
     }
 
+    /// Print each instruction executed by `step` on stderr.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    /// The number of lines available on the terminal,
+    /// the memory view is clamped to keep the prompt visible.
+    pub fn set_height(&mut self, height: Option<usize>) {
+        self.height = height;
+    }
+
+    pub fn set_address_mode(&mut self, mode: AddressMode) {
+        self.address_mode = mode;
+    }
+
+    pub fn set_number_base(&mut self, base: NumberBase) {
+        self.number_base = base;
+    }
+
+    /// The arch width of the interpreters created when loading a program, otherwise
+    /// `DEFAULT_ARCH_WIDTH` or the one suggested by longer programs.
+    pub fn set_default_arch_width(&mut self, arch_width: usize) {
+        self.default_arch_width = Some(arch_width);
+    }
+
+    /// Don't print the informational messages, errors are still printed.
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
     pub fn execute<R: ?Sized + Read, W: ?Sized + DebugWrite>(&mut self, command: &Command, input: &mut R, output: &mut W) /*-> Result<x, y>*/ {
+        let input = &mut EchoInput::new(input, self.echo, self.quiet);
+        // the tail is lent to the output for the duration of the command
+        let mut tail = mem::replace(&mut self.output_tail, OutputTail::new(0));
+        self.execute_command(command, input, &mut TailOutput::new(output, &mut tail));
+        self.output_tail = tail;
+    }
+
+    fn execute_command<R, W>(&mut self, command: &Command, input: &mut R, output: &mut TailOutput<W>)
+        where R: ?Sized + Read, W: ?Sized + DebugWrite {
+
         match *command {
             Command::UnsetInterpreter => {
                 match self.unset_interpreter() {
-                    Ok(_) => printlnc!(yellow: "Interpreter correctly unset."),
+                    Ok(_) => status!(self.quiet, "Interpreter correctly unset."),
                     Err(err) => display_debugger_error(&err),
                 }
             }
             Command::InfosInterpreter => {
                 match self.interpreter() {
-                    Ok(interpreter) => display::display_interpreter_properties(interpreter),
+                    // the properties are what the command asks for, quiet or not
+                    Ok(interpreter) => display::display_interpreter_properties(interpreter, false),
                     Err(err) => display_debugger_error(&err),
                 }
             },
             Command::SetInterpreter{ arch_length, arch_width } => {
                 match self.set_interpreter(arch_length, arch_width) {
                     Ok(_) => {
-                        printlnc!(yellow: "Interpreter created.");
+                        status!(self.quiet, "Interpreter created.");
                         if let Ok(ref interpreter) = self.interpreter() {
-                            display::display_interpreter_properties(interpreter);
+                            display::display_interpreter_properties(interpreter, self.quiet);
                         }
                     },
                     Err(err) => display_debugger_error(&err),
                 }
             }
-            Command::Infos => {
+            Command::Infos { json: true } => {
+                match self.debug_infos() {
+                    Ok(debug) => display::display_infos_json(&debug, self.number_of_cycles),
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
+            Command::Infos { json: false } => {
                 if let Some(ref filename) = self.program_name {
                     println!("Program in execution: '{}'.", filename);
                 }
@@ -94,42 +202,117 @@ impl Debugger {
             },
             Command::Copy(ref filename, ignore_nl) => {
                 self.program_name = Some(filename.clone());
+                self.source = None;
+                self.ignore_nl = ignore_nl;
                 match create_program_from_file(&filename, ignore_nl) {
                     Err(err) => printlnc!(red: "{}", err),
+                    Ok(program) => self.load_program(&program, output),
+                }
+            },
+            Command::Edit => {
+                let filename = match self.program_name {
+                    Some(ref filename) => filename.clone(),
+                    None => return printlnc!(red: "No program file loaded, `copy [file]` first."),
+                };
+                if let Err(err) = edit_file(&filename) {
+                    return printlnc!(red: "{}", err)
+                }
+                match create_program_from_file(&filename, self.ignore_nl) {
+                    Err(err) => printlnc!(red: "{}", err),
+                    Ok(program) => self.load_program(&program, output),
+                }
+            },
+            Command::Asm(ref source) => {
+                self.program_name = None;
+                self.assemble(source.clone(), output);
+            },
+            Command::Assemble(ref filename) => {
+                self.program_name = None;
+                match fs::read_to_string(filename) {
+                    Err(err) => printlnc!(red: "{}", err),
+                    Ok(source) => self.assemble(source, output),
+                }
+            },
+            Command::Diff(ref first, ref second) => {
+                match (create_program_from_file(first, true), create_program_from_file(second, true)) {
+                    (Ok(a), Ok(b)) => display::display_program_diff(&a.op_codes(), &b.op_codes()),
+                    (Err(err), _) | (_, Err(err)) => printlnc!(red: "{}", err),
+                }
+            },
+            Command::Check(ref filename) => {
+                match create_program_from_file(filename, true) {
+                    Ok(program) => {
+                        let arch_length = self.interpreter().map(|i| i.arch_length()).unwrap_or(program.memory().len());
+                        display::display_program_report(&program.validate(arch_length), arch_length)
+                    },
+                    Err(err) => printlnc!(red: "{}", err),
+                }
+            },
+            Command::Checksum(ref filename, expected) => {
+                // the checksum covers the file as is, final newline included
+                match create_program_from_file(filename, false) {
                     Ok(program) => {
-                        match self.copy_program_and_reset(&program) {
-                            Err(_) => { // FIXME if another error than no_interpreter ?!?!
-                                let arch_length = program.memory().len();
-                                match self.set_interpreter(arch_length, DEFAULT_ARCH_WIDTH) {
-                                    Ok(_) => {
-                                        printlnc!(yellow: "Interpreter created.");
-                                        if let Ok(ref interpreter) = self.interpreter() {
-                                            display::display_interpreter_properties(interpreter);
-                                        }
-                                    },
-                                    Err(err) => display_debugger_error(&err),
-                                }
-                                self.copy_program_and_reset(&program).unwrap();
-                                match self.debug_infos() {
-                                    Ok(debug) => self.display_infos(&debug, output),
-                                    Err(err) => display_debugger_error(&err),
-                                }
-                            },
-                            Ok(_) => {
-                                printlnc!(yellow: "Program correctly loaded.");
-                                match self.debug_infos() {
-                                    Ok(debug) => self.display_infos(&debug, output),
-                                    Err(err) => display_debugger_error(&err),
-                                }
-                            },
+                        let checksum = program.checksum();
+                        match expected {
+                            None => println!("{:#010x}", checksum),
+                            Some(expected) if expected == checksum => printlnc!(green: "{:#010x} matches.", checksum),
+                            Some(expected) => printlnc!(red: "{:#010x} doesn't match {:#010x}!", checksum, expected),
                         }
                     },
+                    Err(err) => printlnc!(red: "{}", err),
+                }
+            },
+            Command::DumpMemory(ref filename, format) => {
+                match self.dump_memory(filename, format) {
+                    Ok(length) => status!(self.quiet, "{} cells written to '{}'.", length, filename),
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
+            Command::History => display::display_history(&self.history),
+            Command::Halts => {
+                match self.debug_infos() {
+                    Ok(debug) => display::display_halts(&debug.memory, self.quiet),
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
+            Command::Trace(trace) => {
+                self.set_trace(trace);
+                status!(self.quiet, "Trace {}.", if trace { "enabled" } else { "disabled" });
+            },
+            Command::Set(Setting::TailSize(size)) => {
+                output.tail.set_size(size);
+                status!(self.quiet, "Last {} output bytes kept.", size);
+            },
+            Command::OutputTail(count) => {
+                let bytes = output.tail.last(count.unwrap_or(usize::MAX));
+                display::display_output_tail(&bytes);
+            },
+            Command::Set(Setting::Lines(lines)) => {
+                self.set_height(Some(lines));
+                status!(self.quiet, "Display limited to {} lines.", lines);
+            },
+            Command::SetRegister(register, value) => {
+                match self.set_register(register, value) {
+                    Ok(debug) => self.display_infos(&debug, output),
+                    Err(err) => display_debugger_error(&err),
                 }
             },
+            Command::Set(Setting::Addr(mode)) => {
+                self.address_mode = mode;
+                status!(self.quiet, "Addresses displayed as {:?}.", mode);
+            },
+            Command::Set(Setting::Base(base)) => {
+                self.number_base = base;
+                status!(self.quiet, "Addresses displayed in {:?}.", base);
+            },
+            Command::Set(Setting::Echo(echo)) => {
+                self.echo = echo;
+                status!(self.quiet, "Input echo {}.", if echo { "enabled" } else { "disabled" });
+            },
             Command::Reset => {
                 match self.reset() {
                     Ok(stat) => {
-                        printlnc!(yellow: "Reset.");
+                        status!(self.quiet, "Reset.");
                         self.statement = Some(stat);
                         match self.debug_infos() {
                             Ok(debug) => self.display_infos(&debug, output),
@@ -144,15 +327,86 @@ impl Debugger {
                     Ok((executed, debug, stat)) => {
                         self.statement = stat;
                         if executed == to_execute {
-                            printlnc!(yellow: "{} steps executed.", executed)
+                            status!(self.quiet, "{} steps executed.", executed)
                         } else {
-                            printlnc!(yellow: "{}/{} steps executed.", executed, to_execute)
+                            status!(self.quiet, "{}/{} steps executed.", executed, to_execute)
+                        }
+                        self.display_infos(&debug, output)
+                    },
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
+            Command::Run(max_steps) => {
+                let max_steps = max_steps.unwrap_or(DEFAULT_STEP_LIMIT);
+                let before = match self.debug_infos() {
+                    Ok(debug) => debug.memory,
+                    Err(err) => return display_debugger_error(&err),
+                };
+                match self.steps(max_steps, input, output) {
+                    Ok((executed, debug, stat)) => {
+                        self.statement = stat;
+                        status!(self.quiet, "{} steps executed, changed cells:", executed);
+                        display::display_diff(&before, &debug.memory, CHANGED_CELLS_LIMIT);
+                        self.display_infos(&debug, output)
+                    },
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
+            Command::UntilOutput(max_steps) => {
+                let max_steps = max_steps.unwrap_or(DEFAULT_STEP_LIMIT);
+                match self.until_output(max_steps, input, output) {
+                    Ok((executed, debug, stat, byte)) => {
+                        self.statement = stat;
+                        match byte {
+                            Some(byte) => status!(self.quiet, "Output {:#04x} {:?} after {} steps.", byte, byte as char, executed),
+                            None => status!(self.quiet, "No output after {} steps.", executed),
                         }
                         self.display_infos(&debug, output)
                     },
                     Err(err) => display_debugger_error(&err),
                 }
             },
+            Command::Until { addr, max_steps } => {
+                let max_steps = max_steps.unwrap_or(DEFAULT_STEP_LIMIT);
+                match self.until_pc(addr, max_steps, input, output) {
+                    Ok((executed, debug, stat)) => {
+                        self.statement = stat;
+                        match stat {
+                            _ if debug.pc == addr => status!(self.quiet, "Reached {:#06x} after {} steps.", addr, executed),
+                            Some(Statement(op_codes::HALT, _)) => status!(self.quiet, "Halted before {:#06x} after {} steps.", addr, executed),
+                            _ => status!(self.quiet, "Step limit hit before {:#06x} after {} steps.", addr, executed),
+                        }
+                        self.display_infos(&debug, output)
+                    },
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
+            Command::BreakOnNz(max_steps) => {
+                let max_steps = max_steps.unwrap_or(DEFAULT_STEP_LIMIT);
+                match self.until_nz_change(max_steps, input, output) {
+                    Ok((executed, debug, stat, changed)) => {
+                        self.statement = stat;
+                        if changed {
+                            status!(self.quiet, "nz changed to {} after {} steps.", debug.nz, executed)
+                        } else {
+                            status!(self.quiet, "nz unchanged after {} steps.", executed)
+                        }
+                        self.display_infos(&debug, output)
+                    },
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
+            Command::BreakOp(instruction, max_steps) => {
+                let max_steps = max_steps.unwrap_or(DEFAULT_STEP_LIMIT);
+                match self.until_instruction(instruction, max_steps, input, output) {
+                    Ok((executed, debug, stat)) => {
+                        self.statement = stat;
+                        status!(self.quiet, "{} steps executed.", executed);
+                        self.display_infos(&debug, output)
+                    },
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
             Command::Exit | Command::Repeat => unreachable!(),
         };
     }
@@ -183,22 +437,97 @@ impl Debugger {
         }
     }
 
+    /// Assemble `source` and load it, keeping its source map for the display.
+    fn assemble<D: ?Sized + Debug>(&mut self, source: String, output: &D) {
+        match Program::from_source_with_map(&source) {
+            Err(err) => printlnc!(red: "{}", err),
+            Ok((program, map)) => {
+                self.source = Some((map, source));
+                self.load_program(&program, output);
+            },
+        }
+    }
+
+    /// The number and the text of the source line assembled at `pc`.
+    fn source_line(&self, pc: usize) -> Option<(usize, &str)> {
+        let (ref map, ref text) = *self.source.as_ref()?;
+        let line = map.line(pc)?;
+        text.lines().nth(line - 1).map(|text| (line, text))
+    }
+
+    /// Copy the program in the interpreter, creating
+    /// one of the program length if none exists.
+    fn load_program<D: ?Sized + Debug>(&mut self, program: &Program, output: &D) {
+        if self.interpreter.is_none() {
+            let arch_length = program.memory().len();
+            let arch_width = self.default_arch_width.unwrap_or_else(|| {
+                program.suggested_arch_width().max(DEFAULT_ARCH_WIDTH)
+            });
+            match self.set_interpreter(arch_length, arch_width) {
+                Ok(_) => {
+                    status!(self.quiet, "Interpreter created.");
+                    if let Ok(ref interpreter) = self.interpreter() {
+                        display::display_interpreter_properties(interpreter, self.quiet);
+                    }
+                },
+                Err(err) => {
+                    self.source = None;
+                    return display_debugger_error(&err)
+                },
+            }
+        }
+        match self.copy_program_and_reset(program) {
+            Ok(_) => {
+                status!(self.quiet, "Program correctly loaded.");
+                match self.debug_infos() {
+                    Ok(debug) => self.display_infos(&debug, output),
+                    Err(err) => display_debugger_error(&err),
+                }
+            },
+            Err(err) => {
+                self.source = None;
+                display_debugger_error(&err)
+            },
+        }
+    }
+
     fn copy_program_and_reset(&mut self, program: &Program) -> Result<(), DebuggerError> {
         if let Some(ref mut interpreter) = self.interpreter {
-            interpreter.copy_program(program);
-            interpreter.reset();
+            interpreter.copy_program(program).map_err(DebuggerError::Reustmann)?;
+            self.history.clear();
             Ok(())
         }
         else { Err(DebuggerError::NoInterpreter) }
     }
 
+    fn dump_memory<P: AsRef<Path>>(&self, path: P, format: DumpFormat) -> Result<usize, DebuggerError> {
+        let interpreter = self.interpreter()?;
+        let mut file = io::BufWriter::new(fs::File::create(path).map_err(DebuggerError::Io)?);
+        interpreter.dump_memory(&mut file, format).map_err(DebuggerError::Io)?;
+        file.flush().map_err(DebuggerError::Io)?;
+        Ok(interpreter.arch_length())
+    }
+
     fn reset(&mut self) -> Result<Statement, DebuggerError> {
         if let Some(ref mut interpreter) = self.interpreter {
+            self.history.clear();
             Ok(interpreter.reset())
         }
         else { Err(DebuggerError::NoInterpreter) }
     }
 
+    fn set_register(&mut self, register: Register, value: usize) -> Result<DebugInfos, DebuggerError> {
+        if let Some(ref mut interpreter) = self.interpreter {
+            match register {
+                Register::Pc => interpreter.set_pc(value).map_err(DebuggerError::Reustmann)?,
+                Register::Sp => interpreter.set_sp(value).map_err(DebuggerError::Reustmann)?,
+                Register::Nz => interpreter.set_nz(value != 0),
+            }
+            Ok(interpreter.debug_infos())
+        }
+        else { Err(DebuggerError::NoInterpreter) }
+    }
+
     fn steps<R: ?Sized + Read, W: ?Sized + Write>(&mut self, steps: usize, input: &mut R, output: &mut W)
         -> Result<(usize, DebugInfos, Option<Statement>), DebuggerError> {
 
@@ -206,8 +535,13 @@ impl Debugger {
             let mut statement = None;
             let mut executed = 0;
             for i in 0..steps {
+                let pc = if self.trace { Some(interpreter.debug_view().pc) } else { None };
                 statement = Some(interpreter.step(input, output));
                 if let Some(statement) = statement {
+                    push_history(&mut self.history, self.pc_lines, statement);
+                    if let Some(pc) = pc {
+                        display::display_trace(pc, statement, interpreter.debug_view().nz);
+                    }
                     match statement {
                         Statement(op_codes::HALT, _) => break,
                         _ => (),
@@ -221,13 +555,97 @@ impl Debugger {
         else { Err(DebuggerError::NoInterpreter) }
     }
 
+    /// Execute instructions until `stop` returns `true` after a step, a `HALT` is encountered
+    /// or `max_steps` instructions are executed, each statement is recorded and traced like in `steps`.
+    ///
+    /// `stop` also gets the byte on top of the stack before the step, the one an `OUT` writes.
+    fn run_recorded<R, W, F>(&mut self, max_steps: usize, input: &mut R, output: &mut W, mut stop: F)
+        -> Result<(usize, DebugInfos, Option<Statement>), DebuggerError>
+        where R: ?Sized + Read, W: ?Sized + Write, F: FnMut(&Interpreter, Statement, u8) -> bool {
+
+        if let Some(ref mut interpreter) = self.interpreter {
+            let mut statement = None;
+            let mut executed = 0;
+            while executed < max_steps {
+                let (pc, top) = {
+                    let view = interpreter.debug_view();
+                    (view.pc, view.memory[view.sp])
+                };
+                let stat = interpreter.step(input, output);
+                statement = Some(stat);
+                executed += 1;
+                push_history(&mut self.history, self.pc_lines, stat);
+                if self.trace {
+                    display::display_trace(pc, stat, interpreter.debug_view().nz);
+                }
+                if let Statement(op_codes::HALT, _) = stat { break }
+                if stop(interpreter, stat, top) { break }
+            }
+            self.number_of_cycles += executed;
+            Ok((executed, interpreter.debug_infos(), statement))
+        }
+        else { Err(DebuggerError::NoInterpreter) }
+    }
+
+    fn until_output<R: ?Sized + Read, W: ?Sized + Write>(&mut self, max_steps: usize, input: &mut R, output: &mut W)
+        -> Result<(usize, DebugInfos, Option<Statement>, Option<u8>), DebuggerError> {
+
+        let mut byte = None;
+        let (executed, debug, statement) = self.run_recorded(max_steps, input, output, |_, statement, top| {
+            if let Statement(op_codes::OUT, true) = statement { byte = Some(top) }
+            byte.is_some()
+        })?;
+        Ok((executed, debug, statement, byte))
+    }
+
+    fn until_pc<R: ?Sized + Read, W: ?Sized + Write>(&mut self, addr: usize, max_steps: usize, input: &mut R, output: &mut W)
+        -> Result<(usize, DebugInfos, Option<Statement>), DebuggerError> {
+
+        if let Some(ref interpreter) = self.interpreter {
+            let arch_length = interpreter.arch_length();
+            if addr >= arch_length {
+                return Err(DebuggerError::Reustmann(ReustmannError::AddressOutOfBounds { address: addr, arch_length }))
+            }
+        }
+        self.run_recorded(max_steps, input, output, |interpreter, _, _| interpreter.debug_view().pc == addr)
+    }
+
+    fn until_nz_change<R: ?Sized + Read, W: ?Sized + Write>(&mut self, max_steps: usize, input: &mut R, output: &mut W)
+        -> Result<(usize, DebugInfos, Option<Statement>, bool), DebuggerError> {
+
+        let nz = self.interpreter.as_ref().ok_or(DebuggerError::NoInterpreter)?.debug_view().nz;
+        let (executed, debug, statement) = self.run_recorded(max_steps, input, output, |interpreter, _, _| {
+            interpreter.debug_view().nz != nz
+        })?;
+        let changed = debug.nz != nz;
+        Ok((executed, debug, statement, changed))
+    }
+
+    fn until_instruction<R: ?Sized + Read, W: ?Sized + Write>(&mut self, instruction: Instruction, max_steps: usize, input: &mut R, output: &mut W)
+        -> Result<(usize, DebugInfos, Option<Statement>), DebuggerError> {
+
+        self.run_recorded(max_steps, input, output, |interpreter, _, _| interpreter.peek().2 == instruction)
+    }
+
     fn display_infos<D: ?Sized + Debug>(&self, debug_infos: &DebugInfos, output: &D) {
+        let (pc_lines, sp_lines) = match self.height {
+            Some(height) if height <= RESERVED_LINES => {
+                return display::display_summary(debug_infos, self.number_of_cycles, output, self.source_line(debug_infos.pc),
+                                                (self.address_mode, self.number_base))
+            },
+            Some(height) => {
+                let rows = height - RESERVED_LINES;
+                (min(self.pc_lines, rows), min(self.sp_lines, rows))
+            },
+            None => (self.pc_lines, self.sp_lines),
+        };
         display::display_infos(debug_infos,
                                self.number_of_cycles,
-                               self.statement,
+                               (self.statement, self.interpreter.as_ref().and_then(|i| i.failure_reason())),
                                output,
-                               self.pc_lines,
-                               self.sp_lines)
+                               (pc_lines, sp_lines),
+                               self.source_line(debug_infos.pc),
+                               (self.address_mode, self.number_base))
     }
 
     fn debug_infos(&self) -> Result<DebugInfos, DebuggerError> {
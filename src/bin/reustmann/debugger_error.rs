@@ -1,14 +1,22 @@
+use std::io;
+
+use reustmann::ReustmannError;
+
 #[derive(Debug)]
 pub enum DebuggerError {
     NoInterpreter,
-    InterpreterCreation(&'static str)
+    InterpreterCreation(&'static str),
+    Reustmann(ReustmannError),
+    Io(io::Error)
 }
 
 impl DebuggerError {
     pub fn description(&self) -> &'static str {
         match *self {
             DebuggerError::NoInterpreter => "No interpreter created",
-            DebuggerError::InterpreterCreation(err) => err
+            DebuggerError::InterpreterCreation(err) => err,
+            DebuggerError::Reustmann(err) => err.description(),
+            DebuggerError::Io(_) => "Input/output error"
         }
     }
 }
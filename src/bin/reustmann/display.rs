@@ -1,39 +1,188 @@
+use std::cmp::max;
+use std::collections::VecDeque;
 use std::fmt::Debug;
-use reustmann::{DebugInfos, Statement, Interpreter};
-use reustmann::instruction::{Instruction, LongMnemonic, Mnemonic, OpCode, is_valid_op_code};
+use std::str::FromStr;
+use reustmann::{DebugInfos, FailureReason, ProgramReport, Statement, Interpreter};
+use reustmann::instruction::{op_codes, Instruction, Mnemonic, OpCode, is_valid_op_code};
 
 fn is_visible(c: u8) -> bool {
     c >= 32 && c <= 126
 }
 
-pub fn display_statement(statement: Option<Statement>) {
+pub fn display_statement(statement: Option<Statement>, failure: Option<FailureReason>) {
     if let Some(statement) = statement {
         let Statement(op_code, is_success) = statement;
-        let name: LongMnemonic = Into::<Instruction>::into(op_code).into();
-        println!("Last instruction was '{}' and return '{}'.", name, is_success);
+        match failure {
+            Some(reason) if !is_success => {
+                println!("Last instruction was '{}' and return '{}': {}.",
+                    Instruction::from(op_code), is_success, colorize!(red: "{}", reason));
+            },
+            _ => println!("Last instruction was '{}' and return '{}'.", Instruction::from(op_code), is_success),
+        }
+    }
+}
+
+pub fn display_history(history: &VecDeque<Statement>) {
+    if history.is_empty() {
+        println!("No instruction executed yet.");
     }
+    for (i, &Statement(op_code, is_success)) in history.iter().enumerate() {
+        println!("{:>4}: {} -> {}", i as isize - history.len() as isize, format_op_code(op_code), is_success);
+    }
+}
+
+/// Trace lines are written on stderr to not be mixed with the program output.
+pub fn display_trace(pc: usize, statement: Statement, nz: bool) {
+    let Statement(op_code, is_success) = statement;
+    eprintln!("{:>#06x}: {:<6} -> {}, nz: {}", pc, Instruction::from(op_code), is_success, nz);
 }
 
-pub fn format_program_counter(mem_addr: usize, offset: usize, op_code: OpCode) -> String {
+pub fn format_op_code(op_code: OpCode) -> String {
     let instr: Instruction = op_code.into();
-    let longmnemo: LongMnemonic = instr.into();
-    let mem_addr = format!(colorify!(blue: "{:>#06x}"), mem_addr);
 
     let (op_code, longmnemo) = if is_valid_op_code(op_code) {
         let op = format!("{:#04x},  {} ", op_code, Into::<Mnemonic>::into(instr));
-        let name = format!(colorify!(green: "{:<6}"), longmnemo);
+        let name = colorize!(green: "{:<6}", instr);
         (op, name)
     } else {
         let op = format!("{:#04x}, '{}'", op_code, op_code as char);
-        let name = format!(colorify!(red: "{:<6}"), longmnemo);
+        let name = colorize!(red: "{:<6}", instr);
         (op, name)
     };
 
-    format!("{} <{:+}>: {} ({})", mem_addr, offset, longmnemo, op_code)
+    format!("{} ({})", longmnemo, op_code)
+}
+
+/// How the addresses of the instructions are shown.
+#[derive(Debug, Clone, Copy)]
+pub enum AddressMode {
+    /// The memory address.
+    Absolute,
+    /// The offset from `pc`.
+    Relative,
+    /// The memory address followed by the offset from `pc`.
+    Both,
+}
+
+impl FromStr for AddressMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "absolute" => Ok(AddressMode::Absolute),
+            "relative" => Ok(AddressMode::Relative),
+            "both" => Ok(AddressMode::Both),
+            _ => Err(format!("invalid address mode {:?}, expected absolute, relative or both", s)),
+        }
+    }
+}
+
+/// The base of the memory addresses.
+#[derive(Debug, Clone, Copy)]
+pub enum NumberBase {
+    Hex,
+    Dec,
+}
+
+impl FromStr for NumberBase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(NumberBase::Hex),
+            "dec" => Ok(NumberBase::Dec),
+            _ => Err(format!("invalid number base {:?}, expected hex or dec", s)),
+        }
+    }
+}
+
+fn format_address(mem_addr: usize, base: NumberBase) -> String {
+    match base {
+        NumberBase::Hex => colorize!(blue: "{:>#06x}", mem_addr),
+        NumberBase::Dec => colorize!(blue: "{:>6}", mem_addr),
+    }
 }
 
-pub fn format_stack_pointer(mem_addr: usize, value: u8) -> String {
-    let mem_addr = format!(colorify!(blue: "{:>#06x}"), mem_addr);
+pub fn format_program_counter(mem_addr: usize, offset: usize, op_code: OpCode, mode: AddressMode, base: NumberBase) -> String {
+    let mem_addr = format_address(mem_addr, base);
+    match mode {
+        AddressMode::Absolute => format!("{}: {}", mem_addr, format_op_code(op_code)),
+        AddressMode::Relative => format!("{:>6}: {}", format!("<{:+}>", offset), format_op_code(op_code)),
+        AddressMode::Both => format!("{} <{:+}>: {}", mem_addr, offset, format_op_code(op_code)),
+    }
+}
+
+pub fn display_program_diff(a: &[OpCode], b: &[OpCode]) {
+    display_diff(a, b, usize::MAX)
+}
+
+/// Print the cells that differ, at most `limit` of them
+/// followed by the number of cells left out.
+pub fn display_diff(a: &[OpCode], b: &[OpCode], limit: usize) {
+    let mut changed = 0;
+    for addr in 0..max(a.len(), b.len()) {
+        let (left, right) = (a.get(addr), b.get(addr));
+        if left == right { continue }
+        changed += 1;
+        if changed > limit { continue }
+
+        let mem_addr = colorize!(blue: "{:>#06x}", addr);
+        let left = left.map_or_else(|| format!("{:<18}", "-"), |op| format_op_code(*op));
+        let right = right.map_or_else(|| "-".to_string(), |op| format_op_code(*op));
+        println!("{}: {}  |  {}", mem_addr, left, right);
+    }
+    if changed == 0 {
+        println!("identical");
+    }
+    else if changed > limit {
+        println!("... and {} other cells", changed - limit);
+    }
+}
+
+/// Print the bytes 16 per line, in hexadecimal then as ASCII.
+pub fn display_output_tail(bytes: &[u8]) {
+    if bytes.is_empty() {
+        println!("No output yet.");
+    }
+    for (index, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<_> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let ascii: String = chunk.iter().map(|&byte| if is_visible(byte) { byte as char } else { '.' }).collect();
+        println!("{:>#06x}: {:<47}  |{}|", index * 16, hex.join(" "), ascii);
+    }
+}
+
+/// List the addresses of the `HALT`s, warn if there is none.
+pub fn display_halts(memory: &[OpCode], quiet: bool) {
+    let halts: Vec<_> = memory.iter().enumerate()
+                              .filter(|&(_, &op_code)| op_code == op_codes::HALT)
+                              .map(|(addr, _)| addr)
+                              .collect();
+    if halts.is_empty() {
+        printlnc!(red: "No HALT in memory, the program can only be stopped by the step limit!");
+        return
+    }
+    status!(quiet, "{} HALT found:", halts.len());
+    for addr in halts {
+        println!("{}", format_program_counter(addr, 0, op_codes::HALT, AddressMode::Absolute, NumberBase::Hex));
+    }
+}
+
+pub fn display_program_report(report: &ProgramReport, arch_length: usize) {
+    println!("length: {}, arch length: {}", report.length, arch_length);
+    if report.too_large {
+        printlnc!(red: "The program doesn't fit in the interpreter memory.");
+    }
+    println!("{} bytes executed as Nop", report.invalid_op_codes.len());
+    if report.has_halt() {
+        let halts: Vec<_> = report.halts.iter().map(|addr| format!("{:#06x}", addr)).collect();
+        println!("HALT at {}", halts.join(", "));
+    } else {
+        printlnc!(red: "No HALT, the program can only be stopped by the step limit!");
+    }
+}
+
+pub fn format_stack_pointer(mem_addr: usize, value: u8, base: NumberBase) -> String {
+    let mem_addr = format_address(mem_addr, base);
     if is_visible(value) == true {
         let preview = value as char;
         format!("{} ({:#04x}, '{}')", mem_addr, value, preview)
@@ -43,59 +192,91 @@ pub fn format_stack_pointer(mem_addr: usize, value: u8) -> String {
     }
 }
 
+/// The source line of `pc` shown at the end of its row, empty if there is none.
+fn format_source_line(source_line: Option<(usize, &str)>) -> String {
+    match source_line {
+        Some((line, text)) => format!("    {}", colorize!(green: "; line {}: {}", line, text.trim())),
+        None => String::new(),
+    }
+}
+
 fn display_sides(instr: Option<(usize, (usize, &u8))>,
                  stack: Option<(usize, &u8)>,
-                 indicators: bool) {
+                 indicators: bool,
+                 source_line: Option<(usize, &str)>,
+                 (mode, base): (AddressMode, NumberBase)) {
 
     let pc_side = if let Some((idx, (pc_addr, op_code))) = instr {
-        let pc_side = format_program_counter(pc_addr, idx, *op_code);
-        if indicators == true { format!("{} {}", colorify!(red: "pc"), pc_side) }
+        let pc_side = format_program_counter(pc_addr, idx, *op_code, mode, base);
+        if indicators == true { format!("{} {}", colorize!(red: "pc"), pc_side) }
         else { format!("   {}", pc_side) }
     } else {
         format!("")
     };
     let sp_side = if let Some((sp_addr, value)) = stack {
-        let sp_side = format_stack_pointer(sp_addr, *value);
-        if indicators == true { format!("{} {}", colorify!(red: "sp"), sp_side) }
+        let sp_side = format_stack_pointer(sp_addr, *value, base);
+        if indicators == true { format!("{} {}", colorize!(red: "sp"), sp_side) }
         else { format!("   {}", sp_side) }
     } else {
         format!("")
     };
-    println!("{}    {}", pc_side, sp_side);
+    println!("{}    {}{}", pc_side, sp_side, format_source_line(source_line));
 }
 
-// FIXME ugly really !!!
 pub fn display_infos<D: ?Sized + Debug>(debug_infos: &DebugInfos,
                                         number_of_cycles: usize,
-                                        statement: Option<Statement>,
+                                        (statement, failure): (Option<Statement>, Option<FailureReason>),
                                         output: &D,
-                                        pc_lines: usize,
-                                        sp_lines: usize) {
+                                        (pc_lines, sp_lines): (usize, usize),
+                                        source_line: Option<(usize, &str)>,
+                                        mode: (AddressMode, NumberBase)) {
 
     // if let Some(output) = output {
         // let output = String::from_utf8_lossy(&output);
         println!("Output: {:?}", output);
     // }
 
-    let &DebugInfos{ ref memory, pc, sp, nz } = debug_infos;
-    println!("cycles: {}, pc: {}, sp: {}, nz: {}", number_of_cycles, pc, sp, nz);
-    display_statement(statement);
+    let &DebugInfos{ ref memory, pc, sp, nz, steps } = debug_infos;
+    println!("cycles: {}, steps: {}, pc: {}, sp: {}, nz: {}", number_of_cycles, steps, pc, sp, nz);
+    display_statement(statement, failure);
 
-    let mut instrs = (*memory).iter().enumerate().cycle().skip(pc).take(pc_lines).enumerate();
-    let mut stack = (*memory).iter().enumerate().cycle().skip(sp).take(sp_lines);
+    // the windows wrap around the memory like pc and sp do
+    let len = memory.len();
+    let mut instrs = (0..pc_lines).map(|offset| (offset, ((pc + offset) % len, &memory[(pc + offset) % len])));
+    let mut stack = (0..sp_lines).map(|offset| ((sp + offset) % len, &memory[(sp + offset) % len]));
 
-    display_sides(instrs.next(), stack.next(), true);
+    display_sides(instrs.next(), stack.next(), true, source_line, mode);
     loop {
         match (instrs.next(), stack.next()) {
             (None, None) => break,
-            (instr, stack) => display_sides(instr, stack, false),
+            (instr, stack) => display_sides(instr, stack, false, None, mode),
         }
     }
 }
 
-pub fn display_interpreter_properties(interpreter: &Interpreter) {
-    println!("Interpreter as an arch width of {} and an arch length of {}.",
-        format!(colorify!(yellow: "{}"), interpreter.arch_width()),
-        format!(colorify!(yellow: "{}"), interpreter.arch_length())
+/// A three lines version of `display_infos` for tiny terminals.
+pub fn display_summary<D: ?Sized + Debug>(debug_infos: &DebugInfos,
+                                          number_of_cycles: usize,
+                                          output: &D,
+                                          source_line: Option<(usize, &str)>,
+                                          (mode, base): (AddressMode, NumberBase)) {
+    println!("Output: {:?}", output);
+    let &DebugInfos{ ref memory, pc, sp, nz, steps } = debug_infos;
+    println!("cycles: {}, steps: {}, pc: {}, sp: {}, nz: {}", number_of_cycles, steps, pc, sp, nz);
+    println!("{}{}", format_program_counter(pc, 0, memory[pc], mode, base), format_source_line(source_line));
+}
+
+/// Print the debug infos as a single line JSON object, for scripts.
+pub fn display_infos_json(debug_infos: &DebugInfos, number_of_cycles: usize) {
+    let &DebugInfos{ ref memory, pc, sp, nz, steps } = debug_infos;
+    let memory: Vec<_> = memory.iter().map(|op_code| op_code.to_string()).collect();
+    println!(r#"{{"cycles":{},"steps":{},"pc":{},"sp":{},"nz":{},"memory":[{}]}}"#,
+        number_of_cycles, steps, pc, sp, nz, memory.join(","));
+}
+
+pub fn display_interpreter_properties(interpreter: &Interpreter, quiet: bool) {
+    status!(quiet, "Interpreter as an arch width of {} and an arch length of {}.",
+        interpreter.arch_width(),
+        interpreter.arch_length()
     );
 }
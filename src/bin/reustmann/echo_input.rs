@@ -0,0 +1,27 @@
+use std::io;
+use std::io::Read;
+
+/// An input that prints every byte read from it, when `echo` is set and not `quiet`.
+pub struct EchoInput<'a, R: 'a + ?Sized> {
+    input: &'a mut R,
+    echo: bool,
+    quiet: bool,
+}
+
+impl<'a, R: ?Sized + Read> EchoInput<'a, R> {
+    pub fn new(input: &'a mut R, echo: bool, quiet: bool) -> EchoInput<'a, R> {
+        EchoInput { input, echo, quiet }
+    }
+}
+
+impl<'a, R: ?Sized + Read> Read for EchoInput<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.input.read(buf)?;
+        if self.echo {
+            for &byte in &buf[..read] {
+                status!(self.quiet, "Input {:#04x} {:?}", byte, byte as char);
+            }
+        }
+        Ok(read)
+    }
+}
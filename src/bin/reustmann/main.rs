@@ -1,10 +1,33 @@
-#[macro_use] extern crate colorify;
+#[macro_use(colorify)] extern crate colorify;
 extern crate rustyline;
 extern crate reustmann;
 
+/// Same as `colorify!` but only colorize when colors are enabled,
+/// the formatted `String` is returned.
+macro_rules! colorize {
+    ($color:ident: $fmt:expr) => (colorize!($color: $fmt,));
+    ($color:ident: $fmt:expr, $($arg:tt)*) => {
+        if ::color::enabled() { format!(colorify!($color: $fmt), $($arg)*) }
+        else { format!($fmt, $($arg)*) }
+    };
+}
+
+macro_rules! printlnc {
+    ($color:ident: $($arg:tt)*) => (println!("{}", colorize!($color: $($arg)*)));
+}
+
+/// Print an informational yellow line, unless in quiet mode.
+macro_rules! status {
+    ($quiet:expr, $($arg:tt)*) => (if !$quiet { printlnc!(yellow: $($arg)*) });
+}
+
+mod color;
 mod command;
+mod config;
 mod debugger;
 mod debugger_error;
+mod echo_input;
+mod output_tail;
 mod sink_debug;
 mod display;
 
@@ -12,20 +35,65 @@ use rustyline::completion::FilenameCompleter;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
+use std::env;
+use std::process;
+
+use color::ColorMode;
 use command::Command;
+use config::Config;
 use debugger::Debugger;
 
 fn main() {
+    let config = config::load_config().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        Config::default()
+    });
+
+    let mut color_mode = config.color.unwrap_or(ColorMode::Auto);
+    let mut trace = false;
+    let mut quiet = false;
+    let mut prompt = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => trace = true,
+            "--quiet" => quiet = true,
+            "--prompt" => match args.next() {
+                Some(s) => prompt = Some(s),
+                None => { eprintln!("missing prompt"); process::exit(1) },
+            },
+            "--color" => match args.next().map(|s| s.parse()) {
+                Some(Ok(mode)) => color_mode = mode,
+                Some(Err(e)) => { eprintln!("{}", e); process::exit(1) },
+                None => { eprintln!("missing color mode"); process::exit(1) },
+            },
+            arg => { eprintln!("unknown argument {:?}", arg); process::exit(1) },
+        }
+    }
+    color::set_color_mode(color_mode);
+
     let file_comp = FilenameCompleter::new();
     let mut rustyline = Editor::new();
 
     rustyline.set_completer(Some(&file_comp));
-    if let Err(_) = rustyline.load_history("history.txt") {
-        printlnc!(yellow: "No previous history.");
+    if rustyline.load_history("history.txt").is_err() {
+        status!(quiet, "No previous history.");
     }
 
     let mut last_command = None;
     let mut dbg = Debugger::new();
+    dbg.set_trace(trace);
+    dbg.set_quiet(quiet);
+    dbg.set_height(config.lines.or_else(|| env::var("LINES").ok().and_then(|lines| lines.parse().ok())));
+    if let Some(mode) = config.addr {
+        dbg.set_address_mode(mode);
+    }
+    if let Some(base) = config.base {
+        dbg.set_number_base(base);
+    }
+    if let Some(arch_width) = config.arch_width {
+        dbg.set_default_arch_width(arch_width);
+    }
 
     // FIXME do this elsewhere
     // if let Ok(ref interpreter) = dbg.interpreter() {
@@ -38,8 +106,13 @@ fn main() {
     // let mut output = sink_debug::sink_debug();
     let mut output = Vec::<u8>::new();
 
+    if let Some(arch_length) = config.arch_length {
+        let arch_width = config.arch_width.unwrap_or(debugger::DEFAULT_ARCH_WIDTH);
+        dbg.execute(&Command::SetInterpreter { arch_length, arch_width }, &mut input, &mut output);
+    }
+
+    let prompt = prompt.unwrap_or_else(|| colorize!(dark_grey: "({}) ", "rmdb"));
     loop {
-        let prompt = format!(colorify!(dark_grey: "({}) "), "rmdb");
         let readline = rustyline.readline(&prompt);
         match readline {
             Ok(line) => {
@@ -47,9 +120,9 @@ fn main() {
 
                 let command = match (line.parse(), last_command) {
                     (Ok(Command::Repeat), Some(c)) => Ok(c),
-                    (Ok(Command::Repeat), None) => Err("No last command.".into()),
+                    (Ok(Command::Repeat), None) => Err("No last command.".to_string()),
                     (Ok(c), _) => Ok(c),
-                    (Err(e), _) => Err(e),
+                    (Err(e), _) => Err(e.to_string()),
                 };
 
                 match command {
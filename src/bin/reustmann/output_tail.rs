@@ -0,0 +1,72 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::io::Write;
+
+/// The number of output bytes remembered by default.
+pub const DEFAULT_TAIL_SIZE: usize = 256;
+
+/// A ring buffer of the last bytes written by the program.
+pub struct OutputTail {
+    bytes: VecDeque<u8>,
+    size: usize,
+}
+
+impl OutputTail {
+    pub fn new(size: usize) -> OutputTail {
+        OutputTail { bytes: VecDeque::with_capacity(size), size }
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.size == 0 { return }
+        if self.bytes.len() == self.size {
+            self.bytes.pop_front();
+        }
+        self.bytes.push_back(byte);
+    }
+
+    /// Change the number of bytes remembered, the oldest ones are dropped.
+    pub fn set_size(&mut self, size: usize) {
+        let excess = self.bytes.len().saturating_sub(size);
+        self.bytes.drain(..excess);
+        self.size = size;
+    }
+
+    /// The last `n` bytes, oldest first.
+    pub fn last(&self, n: usize) -> Vec<u8> {
+        let skip = self.bytes.len().saturating_sub(n);
+        self.bytes.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// An output that remembers the bytes written to it in an `OutputTail`.
+pub struct TailOutput<'a, W: 'a + ?Sized> {
+    output: &'a mut W,
+    pub tail: &'a mut OutputTail,
+}
+
+impl<'a, W: ?Sized + Write> TailOutput<'a, W> {
+    pub fn new(output: &'a mut W, tail: &'a mut OutputTail) -> TailOutput<'a, W> {
+        TailOutput { output, tail }
+    }
+}
+
+impl<'a, W: ?Sized + Write> Write for TailOutput<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.output.write(buf)?;
+        for &byte in &buf[..written] {
+            self.tail.push(byte);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+impl<'a, W: ?Sized + fmt::Debug> fmt::Debug for TailOutput<'a, W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.output.fmt(f)
+    }
+}
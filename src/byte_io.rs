@@ -0,0 +1,108 @@
+//! The byte-oriented input and output used by the interpreter.
+//!
+//! The interpreter only ever reads and writes one byte at a time, so it doesn't
+//! depend on `std::io`: with the `std` feature every `Read` is a `ByteIn` and
+//! every `Write` is a `ByteOut`, without it `&[u8]` and `Vec<u8>` are provided.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "mmap")]
+use std::{fs, io};
+#[cfg(feature = "mmap")]
+use std::path::Path;
+
+/// The error returned when the underlying input or output failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteIoError;
+
+/// A source of bytes read by the `IN` instruction.
+pub trait ByteIn {
+    /// Read the next byte, `Ok(None)` at the end of the input.
+    fn read_byte(&mut self) -> Result<Option<u8>, ByteIoError>;
+}
+
+/// A sink of bytes written by the `OUT` instruction.
+pub trait ByteOut {
+    /// Write one byte.
+    fn write_byte(&mut self, byte: u8) -> Result<(), ByteIoError>;
+}
+
+#[cfg(feature = "std")]
+impl<R: ?Sized + std::io::Read> ByteIn for R {
+    fn read_byte(&mut self) -> Result<Option<u8>, ByteIoError> {
+        let mut buffer = [0; 1];
+        match self.read(&mut buffer) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(buffer[0])),
+            Err(_) => Err(ByteIoError),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: ?Sized + std::io::Write> ByteOut for W {
+    fn write_byte(&mut self, byte: u8) -> Result<(), ByteIoError> {
+        self.write(&[byte]).map(|_| ()).map_err(|_| ByteIoError)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<'a> ByteIn for &'a [u8] {
+    fn read_byte(&mut self) -> Result<Option<u8>, ByteIoError> {
+        match self.split_first() {
+            Some((&byte, rest)) => {
+                *self = rest;
+                Ok(Some(byte))
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl ByteOut for Vec<u8> {
+    fn write_byte(&mut self, byte: u8) -> Result<(), ByteIoError> {
+        self.push(byte);
+        Ok(())
+    }
+}
+
+/// An input read from a read-only memory map of a file, for large inputs:
+/// each `IN` takes the next byte of the map, without any read call or buffer.
+#[cfg(feature = "mmap")]
+pub struct MappedInput {
+    map: memmap2::Mmap,
+    position: usize,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedInput {
+    /// Map the file at `path`, the first `IN` reads its first byte.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or another one,
+    /// while the input is alive: the bytes not read yet would change under it
+    /// and reading a truncated part is undefined behavior.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<MappedInput> {
+        let file = fs::File::open(path)?;
+        let map = memmap2::Mmap::map(&file)?;
+        Ok(MappedInput { map, position: 0 })
+    }
+
+    /// The bytes not read yet.
+    pub fn remaining(&self) -> &[u8] {
+        &self.map[self.position..]
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ByteIn for MappedInput {
+    fn read_byte(&mut self) -> Result<Option<u8>, ByteIoError> {
+        let byte = self.map.get(self.position).cloned();
+        if byte.is_some() {
+            self.position += 1;
+        }
+        Ok(byte)
+    }
+}
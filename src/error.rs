@@ -0,0 +1,95 @@
+use core::fmt;
+
+/// Errors returned when loading programs or snapshots in the interpreter or addressing its memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReustmannError {
+    /// The program is longer than the interpreter memory.
+    ProgramTooLarge {
+        program_length: usize,
+        arch_length: usize
+    },
+    /// The address is not inside the interpreter memory.
+    AddressOutOfBounds {
+        address: usize,
+        arch_length: usize
+    },
+    /// The snapshot was taken on an interpreter of another arch length or width.
+    SnapshotMismatch {
+        snapshot_length: usize,
+        snapshot_width: usize,
+        arch_length: usize,
+        arch_width: usize
+    }
+}
+
+impl ReustmannError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            ReustmannError::ProgramTooLarge { .. } => "Program too large for the interpreter memory",
+            ReustmannError::AddressOutOfBounds { .. } => "Address out of the interpreter memory",
+            ReustmannError::SnapshotMismatch { .. } => "Snapshot of an interpreter of another rank",
+        }
+    }
+}
+
+impl fmt::Display for ReustmannError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReustmannError::ProgramTooLarge { program_length, arch_length } => {
+                write!(f, "{} ({} > {})", self.description(), program_length, arch_length)
+            },
+            ReustmannError::AddressOutOfBounds { address, arch_length } => {
+                write!(f, "{} ({} >= {})", self.description(), address, arch_length)
+            },
+            ReustmannError::SnapshotMismatch { snapshot_length, snapshot_width, arch_length, arch_width } => {
+                write!(f, "{} (L = {}, W = {} instead of L = {}, W = {})", self.description(),
+                    snapshot_length, snapshot_width, arch_length, arch_width)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReustmannError {}
+
+/// Errors returned when building an interpreter from an existing memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpreterError {
+    /// The memory has no cell.
+    EmptyMemory,
+    /// The memory has `2^32` cells or more.
+    MemoryTooLarge {
+        arch_length: usize
+    },
+    /// The arch width is not in the range `[6..32)`.
+    InvalidArchWidth {
+        arch_width: usize
+    }
+}
+
+impl InterpreterError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            InterpreterError::EmptyMemory => "Memory need to have at least one cell",
+            InterpreterError::MemoryTooLarge { .. } => "Arch length need to be in the range [1..2^32)",
+            InterpreterError::InvalidArchWidth { .. } => "Arch width need to be in the range [6..32)",
+        }
+    }
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InterpreterError::EmptyMemory => write!(f, "{}", self.description()),
+            InterpreterError::MemoryTooLarge { arch_length } => {
+                write!(f, "{} ({})", self.description(), arch_length)
+            },
+            InterpreterError::InvalidArchWidth { arch_width } => {
+                write!(f, "{} ({})", self.description(), arch_width)
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InterpreterError {}
@@ -0,0 +1,32 @@
+use std::cmp::max;
+use std::io::sink;
+
+use crate::instruction::op_codes::HALT;
+use crate::interpreter::{Interpreter, Statement};
+use crate::program::Program;
+
+/// Run `program_bytes` on a machine of the program length (at least one word)
+/// and width 8 for `max_steps` instructions or until a `HALT`,
+/// `input` is given to the `IN` instructions and the output is discarded.
+///
+/// This is the fuzzing entry point, any bytes are a valid program
+/// and a valid input so this function must never panic.
+pub fn fuzz_one(program_bytes: &[u8], input: &[u8], max_steps: usize) {
+    let program = Program::from_iter(program_bytes.iter().cloned());
+    let arch_length = max(program_bytes.len(), 1);
+    let mut interpreter = match Interpreter::new(arch_length, 8) {
+        Ok(interpreter) => interpreter,
+        Err(_) => return, // program longer than the addressable memory
+    };
+    if interpreter.copy_program(&program).is_err() {
+        return
+    }
+
+    let mut input = input;
+    let mut output = sink();
+    for _ in 0..max_steps {
+        if let Statement(HALT, _) = interpreter.step(&mut input, &mut output) {
+            break
+        }
+    }
+}
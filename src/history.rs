@@ -0,0 +1,95 @@
+use alloc::collections::VecDeque;
+use core::fmt;
+
+use crate::instruction::op_codes::OpCode;
+
+/// Errors returned by `Interpreter::step_back`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryError {
+    /// `Interpreter::enable_history` wasn't called.
+    Disabled,
+    /// No step to undo, the history is full or was cleared by a reset.
+    Empty,
+}
+
+impl HistoryError {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            HistoryError::Disabled => "History is disabled",
+            HistoryError::Empty => "No step to undo",
+        }
+    }
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HistoryError {}
+
+/// The most cells an instruction writes, `SWAP` writes two.
+const MAX_WRITES: usize = 2;
+
+/// The state before a step, only the cells it wrote are saved.
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    pub pc: usize,
+    pub sp: usize,
+    pub nz: bool,
+    pub output_count: usize,
+    pub total_cost: u64,
+    pub instruction_count: u64,
+    writes: [(usize, OpCode); MAX_WRITES],
+    write_count: usize,
+}
+
+impl Entry {
+    pub fn new(pc: usize, sp: usize, nz: bool, output_count: usize, total_cost: u64, instruction_count: u64) -> Entry {
+        Entry { pc, sp, nz, output_count, total_cost, instruction_count, writes: [(0, 0); MAX_WRITES], write_count: 0 }
+    }
+
+    /// The overwritten cells with their old value, the latest write first.
+    pub fn writes(&self) -> impl Iterator<Item=&(usize, OpCode)> {
+        self.writes[..self.write_count].iter().rev()
+    }
+}
+
+/// A ring buffer of the last steps, the oldest are forgotten.
+#[derive(Debug, Clone)]
+pub(crate) struct History {
+    entries: VecDeque<Entry>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History { entries: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, entry: Entry) {
+        if self.capacity == 0 { return }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Save the old value of a cell written by the current step.
+    pub fn record_write(&mut self, addr: usize, old: OpCode) {
+        if let Some(entry) = self.entries.back_mut() {
+            entry.writes[entry.write_count] = (addr, old);
+            entry.write_count += 1;
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Entry> {
+        self.entries.pop_back()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -68,7 +68,10 @@
 //! 9       SKIP9   Skip over the next nine instructions
 //! ```
 
-use std::convert::From;
+use alloc::string::{String, ToString};
+use core::convert::From;
+use core::fmt;
+use core::str::FromStr;
 
 pub mod mnemonics;
 pub mod long_mnemonics;
@@ -80,7 +83,7 @@ pub use self::long_mnemonics::LongMnemonic;
 
 /// These are the opcodes of the Reustmann instruction set,
 /// shown with their single-character mnemonics and long mnemonics.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Instruction {
     /// No-operation, do-nothing
     ///
@@ -664,6 +667,119 @@ pub enum Instruction {
 
 use self::Instruction::*;
 
+const INSTRUCTION_TABLE: [(OpCode, Instruction); 46] = [
+    (op_codes::NOP,     Nop),
+    (op_codes::RESET,   Reset),
+    (op_codes::HALT,    Halt),
+    (op_codes::IN,      In),
+    (op_codes::OUT,     Out),
+    (op_codes::POP,     Pop),
+    (op_codes::DUP,     Dup),
+    (op_codes::PUSHPC,  PushPc),
+    (op_codes::POPPC,   PopPc),
+    (op_codes::POPSP,   PopSp),
+    (op_codes::SPTGT,   SpTgt),
+    (op_codes::PUSHNZ,  PushNz),
+    (op_codes::SWAP,    Swap),
+    (op_codes::PUSH0,   Push0),
+    (op_codes::ADD,     Add),
+    (op_codes::SUB,     Sub),
+    (op_codes::INC,     Inc),
+    (op_codes::DEC,     Dec),
+    (op_codes::MUL,     Mul),
+    (op_codes::DIV,     Div),
+    (op_codes::XOR,     Xor),
+    (op_codes::AND,     And),
+    (op_codes::OR,      Or),
+    (op_codes::SHL,     Shl),
+    (op_codes::SHR,     Shr),
+    (op_codes::NOT,     Not),
+    (op_codes::BZ,      Bz),
+    (op_codes::BNZ,     Bnz),
+    (op_codes::BEQ,     Beq),
+    (op_codes::BGT,     Bgt),
+    (op_codes::BLT,     Blt),
+    (op_codes::BGE,     Bge),
+    (op_codes::LOOP,    Loop),
+    (op_codes::ENDL,    EndL),
+    (op_codes::BRAN,    BraN),
+    (op_codes::BRAP,    BraP),
+    (op_codes::TARGET,  Target),
+    (op_codes::SKIP1,   Skip1),
+    (op_codes::SKIP2,   Skip2),
+    (op_codes::SKIP3,   Skip3),
+    (op_codes::SKIP4,   Skip4),
+    (op_codes::SKIP5,   Skip5),
+    (op_codes::SKIP6,   Skip6),
+    (op_codes::SKIP7,   Skip7),
+    (op_codes::SKIP8,   Skip8),
+    (op_codes::SKIP9,   Skip9),
+];
+
+/// The op code of every instruction, in op code order.
+///
+/// Op codes missing from the table are executed as `Nop`.
+pub fn instruction_table() -> &'static [(OpCode, Instruction)] {
+    &INSTRUCTION_TABLE
+}
+
+/// The names and the op code of an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionInfo {
+    pub instruction: Instruction,
+    pub op_code: OpCode,
+    pub mnemonic: Mnemonic,
+    pub long_mnemonic: LongMnemonic,
+}
+
+impl Instruction {
+    /// Every instruction once, in op code order.
+    pub fn iter() -> impl Iterator<Item=Instruction> {
+        INSTRUCTION_TABLE.iter().map(|&(_, instruction)| instruction)
+    }
+
+    /// The op code of every instruction, in op code order,
+    /// the other values are executed as `Nop`.
+    pub fn all_op_codes() -> &'static [OpCode] {
+        &op_codes::ALL_OP_CODES
+    }
+
+    /// Parse a short mnemonic, `None` if `s` is not a single mnemonic char.
+    ///
+    /// Short mnemonics are case sensitive, `Z` is `Bz` but `z` is `Bnz`.
+    pub fn from_mnemonic(s: &str) -> Option<Instruction> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if is_valid_mnemonic(c) => Some(Instruction::from(c)),
+            _ => None,
+        }
+    }
+
+    /// Parse a case insensitive long mnemonic.
+    pub fn from_long_mnemonic(s: &str) -> Option<Instruction> {
+        // long mnemonics are listed in op code order
+        long_mnemonics::ALL_LONG_MNEMONICS.iter()
+                                          .position(|lmnemo| lmnemo.eq_ignore_ascii_case(s))
+                                          .map(|op_code| Instruction::from(op_code as OpCode))
+    }
+
+    /// Get the names and the op code of the instruction.
+    pub fn info(self) -> InstructionInfo {
+        InstructionInfo {
+            instruction: self,
+            op_code: self.into(),
+            mnemonic: self.into(),
+            long_mnemonic: self.into(),
+        }
+    }
+}
+
+/// Describe the instruction of a source char, `None` if the char
+/// is not a mnemonic and would only be executed as a `Nop`.
+pub fn describe_char(c: char) -> Option<InstructionInfo> {
+    if is_valid_mnemonic(c) { Some(Instruction::from(c).info()) } else { None }
+}
+
 /// Check if a op_code is a direct command
 /// or will be interpreted as NOP
 pub fn is_valid_op_code(op_code: OpCode) -> bool {
@@ -989,3 +1105,184 @@ impl From<Instruction> for &'static str {
         }
     }
 }
+
+/// The groups of the instruction set summary,
+/// with `Io` and `Stack` split out of its "System and stack" group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstructionCategory {
+    /// `Nop`, `Reset` and `Halt`.
+    System,
+    /// `In` and `Out`.
+    Io,
+    /// The instructions moving words on the stack or between the stack and the registers.
+    Stack,
+    /// Arithmetic and bitwise logic on the top of the stack, `Push0` included.
+    Math,
+    /// The branches that skip the next instruction depending on a condition.
+    Conditional,
+    /// The loops, branches to targets and skips.
+    Unconditional,
+}
+
+impl Instruction {
+    /// The group of the instruction in the instruction set summary.
+    pub fn category(self) -> InstructionCategory {
+        match self {
+            Nop | Reset | Halt => InstructionCategory::System,
+            In | Out => InstructionCategory::Io,
+            Pop | Dup | PushPc | PopPc | PopSp | SpTgt | PushNz | Swap => InstructionCategory::Stack,
+            Push0 | Add | Sub | Inc | Dec | Mul | Div
+                | Xor | And | Or | Shl | Shr | Not => InstructionCategory::Math,
+            Bz | Bnz | Beq | Bgt | Blt | Bge => InstructionCategory::Conditional,
+            Loop | EndL | BraN | BraP | Target
+                | Skip1 | Skip2 | Skip3 | Skip4 | Skip5
+                | Skip6 | Skip7 | Skip8 | Skip9 => InstructionCategory::Unconditional,
+        }
+    }
+
+    /// A one line description of the instruction, the one of the instruction set summary.
+    pub fn description(self) -> &'static str {
+        match self {
+            Nop    => "No-operation",
+            Reset  => "Reset",
+            Halt   => "Halt program execution",
+            In     => "Input from stdin",
+            Out    => "Output to stdout",
+            Pop    => "Pop the stack",
+            Dup    => "Duplicate the top of the stack",
+            PushPc => "Push the current PC",
+            PopPc  => "Pop the stack and set PC",
+            PopSp  => "Pop the stack and set SP",
+            SpTgt  => "Set the SP to the next TARGET",
+            PushNz => "Push the NZ flag",
+            Swap   => "Swap the top two stacked words",
+            Push0  => "Push a zero onto the stack",
+            Add    => "Add the top two stacked words",
+            Sub    => "Subtract the top two stacked words",
+            Inc    => "Increment the top of the stack",
+            Dec    => "Decrement the top of the stack",
+            Mul    => "Multiply the top two stacked words",
+            Div    => "Divide the top two stacked words",
+            Xor    => "Bitwise exclusive OR",
+            And    => "Bitwise logical AND",
+            Or     => "Bitwise logical OR",
+            Shl    => "Logical shift left the top stacked word",
+            Shr    => "Logical shift right the top stacked word",
+            Not    => "Bitwise invert the top stacked word",
+            Bz     => "Branch on zero",
+            Bnz    => "Branch on not-zero",
+            Beq    => "Branch on equal",
+            Bgt    => "Branch on greater than",
+            Blt    => "Branch on less than",
+            Bge    => "Branch on greater or equal",
+            Loop   => "Loop until the following ENDL",
+            EndL   => "End of LOOP",
+            BraN   => "Branch to next TARGET opcode",
+            BraP   => "Branch to previous TARGET opcode",
+            Target => "Branch target for BRAN, BRAP",
+            Skip1  => "Skip over the next instruction",
+            Skip2  => "Skip over the next two instructions",
+            Skip3  => "Skip over the next three instructions",
+            Skip4  => "Skip over the next four instructions",
+            Skip5  => "Skip over the next five instructions",
+            Skip6  => "Skip over the next six instructions",
+            Skip7  => "Skip over the next seven instructions",
+            Skip8  => "Skip over the next eight instructions",
+            Skip9  => "Skip over the next nine instructions",
+        }
+    }
+}
+
+/// The error of parsing an `Instruction` from a string that is not a mnemonic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseInstructionError {
+    /// The string that was parsed.
+    pub token: String,
+}
+
+impl ParseInstructionError {
+    pub fn description(&self) -> &'static str {
+        "Invalid mnemonic"
+    }
+}
+
+impl fmt::Display for ParseInstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:?}", self.description(), self.token)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseInstructionError {}
+
+impl FromStr for Instruction {
+    type Err = ParseInstructionError;
+
+    /// Parse a short or a case insensitive long mnemonic.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Instruction::from_mnemonic(s).or_else(|| Instruction::from_long_mnemonic(s))
+                                     .ok_or_else(|| ParseInstructionError { token: s.to_string() })
+    }
+}
+
+impl fmt::Display for Instruction {
+    /// Display the long mnemonic of the instruction,
+    /// `Mnemonic` and `LongMnemonic` are already displayable.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(Into::<LongMnemonic>::into(*self))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+
+    use super::*;
+
+    #[test]
+    fn iter_yields_every_mnemonic_once() {
+        let distinct_mnemonics: BTreeSet<_> = mnemonics::ALL_MNEMONICS.iter().collect();
+        let instructions: BTreeSet<_> = Instruction::iter().collect();
+        assert_eq!(Instruction::iter().count(), distinct_mnemonics.len());
+        assert_eq!(instructions.len(), distinct_mnemonics.len());
+        assert!(Instruction::all_op_codes().iter().map(|&op_code| Instruction::from(op_code)).eq(Instruction::iter()));
+    }
+
+    #[test]
+    fn mnemonics_round_trip() {
+        for instruction in Instruction::iter() {
+            let mnemonic: Mnemonic = instruction.into();
+            assert_eq!(Instruction::from_mnemonic(&mnemonic.to_string()), Some(instruction));
+
+            let long_mnemonic: LongMnemonic = instruction.into();
+            for spelling in &[long_mnemonic.to_string(), long_mnemonic.to_lowercase(), long_mnemonic.to_uppercase()] {
+                assert_eq!(Instruction::from_long_mnemonic(spelling), Some(instruction), "{}", spelling);
+            }
+        }
+        assert_eq!(Instruction::from_mnemonic(""), None);
+        assert_eq!(Instruction::from_mnemonic("OO"), None);
+        assert_eq!(Instruction::from_long_mnemonic("Bogus"), None);
+    }
+
+    #[test]
+    fn parse_short_long_and_invalid() {
+        assert_eq!("+".parse::<Instruction>(), Ok(Add));
+        assert_eq!("ADD".parse::<Instruction>(), Ok(Add));
+        assert_eq!("PushPc".parse::<Instruction>(), Ok(PushPc));
+        let err = "ADDD".parse::<Instruction>().unwrap_err();
+        assert_eq!(err, ParseInstructionError { token: "ADDD".to_string() });
+        assert_eq!(err.to_string(), "Invalid mnemonic \"ADDD\"");
+    }
+
+    #[test]
+    fn categories_cover_every_instruction() {
+        use self::InstructionCategory::*;
+
+        let categories: HashSet<_> = Instruction::iter().map(Instruction::category).collect();
+        let expected: HashSet<_> = [System, Io, Stack, Math, Conditional, Unconditional].iter().cloned().collect();
+        assert_eq!(categories, expected);
+        assert!(Instruction::iter().all(|instruction| !instruction.description().is_empty()));
+        assert_eq!((Out.category(), Swap.category(), Not.category()), (Io, Stack, Math));
+        assert_eq!((Bge.category(), Skip9.category()), (Conditional, Unconditional));
+    }
+}
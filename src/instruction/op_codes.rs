@@ -1,5 +1,54 @@
 pub type OpCode = u8;
 
+pub const ALL_OP_CODES: [OpCode; 46] = [
+    NOP,
+    RESET,
+    HALT,
+    IN,
+    OUT,
+    POP,
+    DUP,
+    PUSHPC,
+    POPPC,
+    POPSP,
+    SPTGT,
+    PUSHNZ,
+    SWAP,
+    PUSH0,
+    ADD,
+    SUB,
+    INC,
+    DEC,
+    MUL,
+    DIV,
+    XOR,
+    AND,
+    OR,
+    SHL,
+    SHR,
+    NOT,
+    BZ,
+    BNZ,
+    BEQ,
+    BGT,
+    BLT,
+    BGE,
+    LOOP,
+    ENDL,
+    BRAN,
+    BRAP,
+    TARGET,
+    SKIP1,
+    SKIP2,
+    SKIP3,
+    SKIP4,
+    SKIP5,
+    SKIP6,
+    SKIP7,
+    SKIP8,
+    SKIP9,
+];
+
 pub const NOP: OpCode    = 0;
 pub const RESET: OpCode  = 1;
 pub const HALT: OpCode   = 2;
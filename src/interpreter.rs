@@ -1,12 +1,23 @@
-use std::cmp::min;
-use std::io::{Read, Write};
-use std::u32;
+use core::fmt;
+use core::ops::ControlFlow;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::io;
 
-use crate::instruction::Instruction;
-use crate::instruction::is_valid_mnemonic;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::byte_io::{ByteIn, ByteOut};
+use crate::error::{InterpreterError, ReustmannError};
+use crate::history::{Entry, History, HistoryError};
+use crate::instruction::{Instruction, is_valid_op_code};
 use crate::instruction::op_codes::*;
 use crate::memory::OpCodes;
-use crate::program::Program;
+use crate::program::{Program, op_code_of};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Type used to return the execution status of a command
 pub type ExecutionSucceeded = bool;
@@ -15,61 +26,456 @@ pub type ExecutionSucceeded = bool;
 #[derive(Debug, Copy, Clone)]
 pub struct Statement(pub OpCode, pub ExecutionSucceeded);
 
+/// An instruction executed by `Interpreter::events`.
+#[derive(Debug, Clone, Copy)]
+pub struct StepEvent {
+    /// The address of the executed instruction.
+    pub pc: usize,
+    pub statement: Statement,
+    /// The byte written by an `OUT`.
+    pub output: Option<u8>,
+}
+
+/// How a run bounded by a number of steps ended.
+#[derive(Debug, Clone, Copy)]
+pub enum RunOutcome {
+    /// A `HALT` was executed after this number of instructions.
+    Halted(usize),
+    /// This number of instructions was executed without halting.
+    LimitReached(usize),
+    /// The output limit was reached by the `OUT` ending this number of instructions.
+    OutputLimitReached(usize),
+    /// This instruction failed and stopped the run.
+    Failed(Statement),
+    /// The program wasn't run, `run_batch` couldn't load it in an interpreter for this reason.
+    Rejected(&'static str),
+}
+
+/// Why the last step returned a failed `Statement`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureReason {
+    /// `IN` couldn't read from the input.
+    InputError,
+    /// `IN` found the input exhausted with `EofBehavior::NoChange`.
+    InputExhausted,
+    /// `OUT` couldn't write to the output.
+    OutputError,
+    /// `OUT` was skipped because the output limit is reached.
+    OutputLimitReached,
+    /// The instruction tried to write a protected cell.
+    WriteProtected,
+}
+
+impl FailureReason {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            FailureReason::InputError => "input error",
+            FailureReason::InputExhausted => "input exhausted",
+            FailureReason::OutputError => "output error",
+            FailureReason::OutputLimitReached => "output limit reached",
+            FailureReason::WriteProtected => "write to a protected cell",
+        }
+    }
+}
+
+impl fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// The formats of `Interpreter::dump_memory`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// The hexadecimal op codes, 16 cells per line after the address of the first one.
+    Hex,
+    /// Like `Hex` with the printable op codes in an ASCII gutter.
+    HexAscii,
+    /// The raw op codes, one byte per cell.
+    Raw,
+}
+
+/// Number of memory cells on each line of a hexadecimal dump.
+#[cfg(feature = "std")]
+const DUMP_LINE_CELLS: usize = 16;
+
+/// What `IN` does when the input is exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EofBehavior {
+    /// Push `0`, the statement succeeds. This is the default.
+    PushZero,
+    /// Push this value, the statement succeeds.
+    PushValue(u8),
+    /// Act as a `HALT`: `pc` stays on the `IN` and the statement is `Statement(HALT, true)`.
+    Halt,
+    /// Leave the stack and `nz` unchanged and only increment `pc`, the statement fails.
+    NoChange,
+}
+
+impl From<Option<u8>> for EofBehavior {
+    /// The `eof_value` of the builder: `None` is `NoChange`.
+    fn from(eof_value: Option<u8>) -> EofBehavior {
+        match eof_value {
+            Some(0) => EofBehavior::PushZero,
+            Some(val) => EofBehavior::PushValue(val),
+            None => EofBehavior::NoChange,
+        }
+    }
+}
+
 /// A Debug structure to help debugging :)
-#[derive(Debug)]
+///
+/// Its `Debug` output only shows the first cells of the memory.
 pub struct DebugInfos {
     pub memory: OpCodes,
     pub pc: usize,
     pub sp: usize,
-    pub nz: bool
+    pub nz: bool,
+    /// The number of instructions executed since the last reset.
+    pub steps: u64
+}
+
+/// Number of memory cells shown by the `Debug` output of `DebugInfos`.
+const DEBUG_MEMORY_HEAD: usize = 16;
+
+struct MemorySummary<'a>(&'a [OpCode]);
+
+impl<'a> fmt::Debug for MemorySummary<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{} cells:", self.0.len())?;
+        for op_code in self.0.iter().take(DEBUG_MEMORY_HEAD) {
+            write!(f, " {:02x}", op_code)?;
+        }
+        if self.0.len() > DEBUG_MEMORY_HEAD {
+            write!(f, " ...")?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl fmt::Debug for DebugInfos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DebugInfos")
+            .field("memory", &MemorySummary(&self.memory))
+            .field("pc", &self.pc)
+            .field("sp", &self.sp)
+            .field("nz", &self.nz)
+            .field("steps", &self.steps)
+            .finish()
+    }
+}
+
+/// A borrowed version of `DebugInfos`, nothing is copied but the registers.
+///
+/// The interpreter can't step while the view is alive,
+/// use `DebugInfos` to keep a snapshot of the memory.
+#[derive(Clone, Copy)]
+pub struct DebugView<'a> {
+    pub memory: &'a [OpCode],
+    pub pc: usize,
+    pub sp: usize,
+    pub nz: bool,
+    pub steps: u64
+}
+
+impl<'a> fmt::Debug for DebugView<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DebugView")
+            .field("memory", &MemorySummary(self.memory))
+            .field("pc", &self.pc)
+            .field("sp", &self.sp)
+            .field("nz", &self.nz)
+            .field("steps", &self.steps)
+            .finish()
+    }
+}
+
+/// The whole state of an interpreter, its options included,
+/// serializable with the `serde` feature to checkpoint interpreters.
+///
+/// `Interpreter::from_state` checks it, so a state edited or read from
+/// an untrusted source can't build an invalid interpreter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InterpreterState {
+    arch_width: u8,
+    memory: Vec<OpCode>,
+    image: Vec<OpCode>,
+    eof: EofBehavior,
+    output_limit: Option<usize>,
+    output_count: usize,
+    costs: Option<Vec<u32>>,
+    total_cost: u64,
+    instruction_count: u64,
+    fold_nops: bool,
+    pc: usize,
+    sp: usize,
+    nz: bool
+}
+
+/// The memory and the registers of an interpreter, to go back to them with `restore`.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    arch_width: u8,
+    memory: Vec<OpCode>,
+    pc: usize,
+    sp: usize,
+    nz: bool
 }
 
 /// The main interpreter, execute instructions, read from input,
 /// write to output
+///
+/// A clone has its own copy of the memory, the loaded program and the registers.
+#[derive(Clone)]
 pub struct Interpreter {
     arch_width: u8,      // [6..32)
     memory: Vec<OpCode>, // [1..2^32)
+    image: Vec<OpCode>,  // memory as it was after the last program copy
+    eof: EofBehavior,
+    output_limit: Option<usize>,
+    output_count: usize,
+    costs: Option<Vec<u32>>, // indexed by op code
+    total_cost: u64,
+    instruction_count: u64,
+    fold_nops: bool,
+    breakpoints: Vec<usize>, // sorted addresses
+    history: Option<History>,
+    protected: Vec<(usize, usize)>, // [start..end) ranges
+    write_blocked: bool, // a write of the current step hit a protected cell
+    failure: Option<FailureReason>, // why the last step failed
+    dirty: Vec<usize>,   // cells written since the last take_dirty
+    is_dirty: Vec<bool>, // indexed by address, avoid duplicates in dirty
     pc: usize,
     sp: usize,
     nz: bool
 }
 
-impl Interpreter {
-    /// Construct a new Interpreter with an existing Program.
-    ///
+/// Construct an Interpreter with non-default options.
+pub struct InterpreterBuilder {
+    arch_length: usize,
+    arch_width: usize,
+    eof: EofBehavior,
+    output_limit: Option<usize>,
+    costs: Option<Vec<u32>>,
+    fold_nops: bool
+}
+
+impl InterpreterBuilder {
     /// `arch_length` need to be in the range `[1..2^32)`
-    /// and `arch_width` in `[6..32)`.
-    pub fn new(arch_length: usize, arch_width: usize) -> Result<Interpreter, &'static str> {
+    /// and `arch_width` in `[6..32)`, this is checked by `build`.
+    pub fn new(arch_length: usize, arch_width: usize) -> InterpreterBuilder {
+        InterpreterBuilder {
+            arch_length,
+            arch_width,
+            eof: EofBehavior::PushZero,
+            output_limit: None,
+            costs: None,
+            fold_nops: false
+        }
+    }
+
+    /// The value pushed by `IN` when the input is exhausted, defaults to `Some(0)`.
+    ///
+    /// With `None` an `IN` at end of input is a failed no-op:
+    /// the stack is left unchanged and only `pc` is incremented.
+    pub fn eof_value(mut self, eof_value: Option<u8>) -> InterpreterBuilder {
+        self.eof = eof_value.into();
+        self
+    }
+
+    /// What `IN` does when the input is exhausted, defaults to `EofBehavior::PushZero`.
+    pub fn eof_behavior(mut self, eof: EofBehavior) -> InterpreterBuilder {
+        self.eof = eof;
+        self
+    }
+
+    /// The maximum number of bytes written by `OUT` since the last reset,
+    /// defaults to `None` (unlimited).
+    ///
+    /// Once the limit is reached `run` stops and any other `OUT`
+    /// is a failed no-op that doesn't write anything.
+    pub fn output_limit(mut self, output_limit: Option<usize>) -> InterpreterBuilder {
+        self.output_limit = output_limit;
+        self
+    }
+
+    /// The cost of each instruction accumulated by `total_cost`,
+    /// instructions missing from the table cost `1`.
+    ///
+    /// Op codes executed as a `Nop` cost the same as `Nop`,
+    /// without a table every instruction costs `1`.
+    #[cfg(feature = "std")]
+    pub fn cost_table(mut self, cost_table: HashMap<Instruction, u32>) -> InterpreterBuilder {
+        let cost_of = |instruction| cost_table.get(&instruction).cloned().unwrap_or(1);
+        let mut costs: Vec<_> = (0..256).map(|_| cost_of(Instruction::Nop)).collect();
+        for &(op_code, instruction) in crate::instruction::instruction_table() {
+            costs[op_code as usize] = cost_of(instruction);
+        }
+        self.costs = Some(costs);
+        self
+    }
+
+    /// Let `run` execute a run of consecutive `NOP`s in a single step, defaults to `false`.
+    ///
+    /// The `NOP`s are still counted in the number of instructions executed
+    /// and in `total_cost`, but the `run_with_control` hook is only called
+    /// once per run, with a `NOP` statement.
+    /// Op codes executed as a `NOP` are part of the runs.
+    pub fn fold_nops(mut self, fold_nops: bool) -> InterpreterBuilder {
+        self.fold_nops = fold_nops;
+        self
+    }
+
+    pub fn build(self) -> Result<Interpreter, &'static str> {
+        let InterpreterBuilder { arch_length, arch_width, eof, output_limit, costs, fold_nops } = self;
         if arch_length == 0 || arch_length > u32::MAX as usize {
             return Err("Arch length need to be in the range [1..2^32)");
         }
         if arch_width < 6 || arch_width > 32 {
             return Err("Arch width need to be in the range [6..32)");
         }
-        let mut memory = Vec::with_capacity(arch_length);
-        for _ in 0..arch_length {
-            memory.push(NOP);
-        }
+        let memory = vec![NOP; arch_length];
         Ok(Interpreter {
             arch_width: arch_width as u8,
+            image: memory.clone(),
             memory,
+            eof,
+            output_limit,
+            output_count: 0,
+            costs,
+            total_cost: 0,
+            instruction_count: 0,
+            fold_nops,
+            breakpoints: Vec::new(),
+            history: None,
+            protected: Vec::new(),
+            write_blocked: false,
+            failure: None,
+            dirty: Vec::new(),
+            is_dirty: vec![false; arch_length],
             pc: 0,
             sp: 0,
             nz: false
         })
     }
+}
+
+impl Interpreter {
+    /// Construct a new Interpreter with an existing Program.
+    ///
+    /// `arch_length` need to be in the range `[1..2^32)`
+    /// and `arch_width` in `[6..32)`.
+    pub fn new(arch_length: usize, arch_width: usize) -> Result<Interpreter, &'static str> {
+        InterpreterBuilder::new(arch_length, arch_width).build()
+    }
+
+    /// Construct an Interpreter whose memory is `memory`, an arch length of `memory.len()`.
+    ///
+    /// The bytes are op codes, not mnemonics, and become the image that `reset` restores,
+    /// the registers are the ones of a reset.
+    pub fn with_memory(memory: Vec<OpCode>, arch_width: usize) -> Result<Interpreter, InterpreterError> {
+        if memory.is_empty() {
+            return Err(InterpreterError::EmptyMemory);
+        }
+        if memory.len() > u32::MAX as usize {
+            return Err(InterpreterError::MemoryTooLarge { arch_length: memory.len() });
+        }
+        if !(6..=32).contains(&arch_width) {
+            return Err(InterpreterError::InvalidArchWidth { arch_width });
+        }
+        // the checks above are the ones of `InterpreterBuilder::build`
+        let mut interpreter = Interpreter::new(memory.len(), arch_width).unwrap();
+        interpreter.image.copy_from_slice(&memory);
+        interpreter.memory = memory;
+        Ok(interpreter)
+    }
+
+    /// Rebuild an interpreter from a state given by `state`,
+    /// the cells written since the state was taken are not tracked
+    /// and there is no breakpoint nor protected range.
+    pub fn from_state(state: InterpreterState) -> Result<Interpreter, &'static str> {
+        let InterpreterState { arch_width, memory, image, eof, output_limit,
+                               output_count, costs, total_cost, instruction_count, fold_nops, pc, sp, nz } = state;
+        let arch_length = memory.len();
+        // checks the arch length and width
+        Interpreter::new(arch_length, arch_width as usize)?;
+        if image.len() != arch_length {
+            return Err("Image and memory need to have the same length");
+        }
+        if pc >= arch_length || sp >= arch_length {
+            return Err("Pc and sp need to be in the range [0..arch_length)");
+        }
+        if costs.as_ref().is_some_and(|costs| costs.len() != 256) {
+            return Err("Cost table need to have a cost for each of the 256 op codes");
+        }
+        Ok(Interpreter {
+            arch_width,
+            memory,
+            image,
+            eof,
+            output_limit,
+            output_count,
+            costs,
+            total_cost,
+            instruction_count,
+            fold_nops,
+            breakpoints: Vec::new(),
+            history: None,
+            protected: Vec::new(),
+            write_blocked: false,
+            failure: None,
+            dirty: Vec::new(),
+            is_dirty: vec![false; arch_length],
+            pc,
+            sp,
+            nz
+        })
+    }
 
     /// Copy your program in the memory of the machine, a reset is done after
-    /// program was loaded.
-    pub fn copy_program(&mut self, program: &Program) {
-        let mnemos = program.memory();
-        for i in 0..min(mnemos.len(), self.memory.len()) {
-            let mnemo = mnemos[i] as char;
-            self.memory[i] = if is_valid_mnemonic(mnemo) {
-                Into::<Instruction>::into(mnemo).into()
-            } else { mnemo as u8 };
+    /// program was loaded, except for the instruction count that keeps running.
+    ///
+    /// The cells following the program are filled with `NOP`s and the whole
+    /// memory becomes the image that `reset` restores.
+    /// The program must not be longer than the arch length.
+    pub fn copy_program(&mut self, program: &Program) -> Result<(), ReustmannError> {
+        self.copy_program_bytes(program.memory())
+    }
+
+    /// Same as `copy_program` but directly from the program source bytes.
+    pub fn copy_program_bytes(&mut self, mnemos: &[u8]) -> Result<(), ReustmannError> {
+        if mnemos.len() > self.image.len() {
+            return Err(ReustmannError::ProgramTooLarge {
+                program_length: mnemos.len(),
+                arch_length: self.image.len()
+            });
         }
-        self.reset();
+        let (code, rest) = self.image.split_at_mut(mnemos.len());
+        for (op_code, &mnemo) in code.iter_mut().zip(mnemos) {
+            *op_code = op_code_of(mnemo);
+        }
+        rest.fill(NOP);
+        self.restart();
+        Ok(())
+    }
+
+    /// Copy `program` in place of the current one, like a new interpreter
+    /// with the same arch length and options: the instruction count
+    /// and the written cells are cleared too.
+    ///
+    /// The allocations are reused and nothing is allocated:
+    /// keep one interpreter to evaluate many programs.
+    /// The program must not be longer than the arch length.
+    pub fn reset_with_program(&mut self, program: &Program) -> Result<(), ReustmannError> {
+        self.copy_program(program)?;
+        for &addr in &self.dirty {
+            self.is_dirty[addr] = false;
+        }
+        self.dirty.clear();
+        self.instruction_count = 0;
+        Ok(())
     }
 
     /// return the interpreter arch length
@@ -82,9 +488,208 @@ impl Interpreter {
         self.arch_width as usize
     }
 
-    /// Reset `pc`, `sp` and `nz` to `0`, `0` and `false` respectively.
-    #[inline]
+    /// Restore the memory image of the last copied program
+    /// and reset `pc`, `sp` and `nz` to `0`, `0` and `false` respectively.
+    ///
+    /// Self-modifying programs therefore always restart from their original code,
+    /// the `RESET` instruction on the other hand only touches the registers.
+    ///
+    /// The instruction count is set back to zero, unlike with `copy_program`.
     pub fn reset(&mut self) -> Statement {
+        self.instruction_count = 0;
+        self.restart()
+    }
+
+    /// Same as `reset` but keep the instruction count.
+    fn restart(&mut self) -> Statement {
+        self.clear_history();
+        self.failure = None;
+        self.memory.copy_from_slice(&self.image);
+        self.output_count = 0;
+        self.total_cost = 0;
+        self.reset_registers()
+    }
+
+    /// Why the last step failed, `None` if it succeeded.
+    ///
+    /// Reustmann instructions can't underflow the stack or divide by zero,
+    /// only the input, the output and the protected cells make a step fail.
+    pub fn failure_reason(&self) -> Option<FailureReason> {
+        self.failure
+    }
+
+    /// The number of instructions executed since the last `reset`.
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Change what `IN` does when the input is exhausted.
+    pub fn set_eof_behavior(&mut self, eof: EofBehavior) {
+        self.eof = eof;
+    }
+
+    /// Return `true` if `OUT` wrote as many bytes as the output limit allows.
+    pub fn output_limit_reached(&self) -> bool {
+        self.output_limit.is_some_and(|limit| self.output_count >= limit)
+    }
+
+    /// The sum of the costs of the instructions executed since the last reset,
+    /// the number of steps when the interpreter was built without a cost table.
+    pub fn total_cost(&self) -> u64 {
+        self.total_cost
+    }
+
+    /// Return and forget the addresses of the cells written by instructions
+    /// since the last call, in the order of their first write.
+    ///
+    /// `reset` and `copy_program` rewrite the whole memory and are not tracked.
+    pub fn take_dirty(&mut self) -> Vec<usize> {
+        for &addr in &self.dirty {
+            self.is_dirty[addr] = false;
+        }
+        core::mem::take(&mut self.dirty)
+    }
+
+    /// Read the cell at `addr`, `None` if `addr` is not inside the memory.
+    ///
+    /// Addresses don't wrap modulo the arch length like `sp` does,
+    /// the last cell is at `arch_length() - 1`.
+    pub fn get_memory(&self, addr: usize) -> Option<u8> {
+        self.memory.get(addr).cloned()
+    }
+
+    /// Write the cell at `addr`, `addr` must be inside the memory.
+    ///
+    /// The cell is tracked by `take_dirty`, the loaded program that `reset` restores is left as is.
+    pub fn set_memory(&mut self, addr: usize, value: u8) -> Result<(), ReustmannError> {
+        self.check_address(addr)?;
+        self.memory[addr] = value;
+        self.mark_dirty(addr);
+        Ok(())
+    }
+
+    /// Make the `len` cells from `start` read-only for the instructions,
+    /// the range must be inside the memory, it doesn't wrap.
+    ///
+    /// An instruction writing a protected cell does everything else but the write
+    /// and its statement fails, which stops `run`. `set_memory` can still write them.
+    pub fn protect_range(&mut self, start: usize, len: usize) -> Result<(), ReustmannError> {
+        if len == 0 { return Ok(()) }
+        self.check_address(start)?;
+        self.check_address(start.saturating_add(len - 1))?;
+        self.protected.push((start, start + len));
+        Ok(())
+    }
+
+    pub fn unprotect_all(&mut self) {
+        self.protected.clear();
+    }
+
+    /// Return `true` if the instructions can't write the cell at `addr`.
+    #[inline]
+    pub fn is_protected(&self, addr: usize) -> bool {
+        self.protected.iter().any(|&(start, end)| start <= addr && addr < end)
+    }
+
+    /// Make `run` stop before executing the instruction at `addr`,
+    /// `addr` must be inside the memory.
+    pub fn add_breakpoint(&mut self, addr: usize) -> Result<(), ReustmannError> {
+        self.check_address(addr)?;
+        if let Err(index) = self.breakpoints.binary_search(&addr) {
+            self.breakpoints.insert(index, addr);
+        }
+        Ok(())
+    }
+
+    /// Return `true` if there was a breakpoint at `addr`.
+    pub fn remove_breakpoint(&mut self, addr: usize) -> bool {
+        match self.breakpoints.binary_search(&addr) {
+            Ok(index) => { self.breakpoints.remove(index); true },
+            Err(_) => false,
+        }
+    }
+
+    /// Return `true` if `run` stops before executing the instruction at `addr`.
+    #[inline]
+    pub fn is_breakpoint(&self, addr: usize) -> bool {
+        !self.breakpoints.is_empty() && self.breakpoints.binary_search(&addr).is_ok()
+    }
+
+    /// Remember the state before each of the next `capacity` steps, to undo them with `step_back`.
+    ///
+    /// Only the registers and the cells written are saved, the history is cleared
+    /// by `reset`, `copy_program` and `restore`. Nothing is recorded until it is enabled.
+    pub fn enable_history(&mut self, capacity: usize) {
+        self.history = Some(History::new(capacity));
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Undo the last step, the memory, the registers and the counters are restored.
+    ///
+    /// The input read and the output written can't be taken back.
+    /// A run of `NOP`s executed at once by `fold_nops` is undone at once.
+    pub fn step_back(&mut self) -> Result<(), HistoryError> {
+        let history = self.history.as_mut().ok_or(HistoryError::Disabled)?;
+        let entry = history.pop().ok_or(HistoryError::Empty)?;
+        for &(addr, old) in entry.writes() {
+            self.memory[addr] = old;
+        }
+        self.pc = entry.pc;
+        self.sp = entry.sp;
+        self.nz = entry.nz;
+        self.output_count = entry.output_count;
+        self.total_cost = entry.total_cost;
+        self.instruction_count = entry.instruction_count;
+        Ok(())
+    }
+
+    fn history_entry(&self) -> Entry {
+        Entry::new(self.pc, self.sp, self.nz, self.output_count, self.total_cost, self.instruction_count)
+    }
+
+    fn push_history_entry(&mut self) {
+        let entry = self.history_entry();
+        if let Some(ref mut history) = self.history {
+            history.push(entry);
+        }
+    }
+
+    fn clear_history(&mut self) {
+        if let Some(ref mut history) = self.history {
+            history.clear();
+        }
+    }
+
+    /// Move the program counter, `addr` must be inside the memory.
+    pub fn set_pc(&mut self, addr: usize) -> Result<(), ReustmannError> {
+        self.check_address(addr)?;
+        self.pc = addr;
+        Ok(())
+    }
+
+    /// Move the stack pointer, `addr` must be inside the memory.
+    pub fn set_sp(&mut self, addr: usize) -> Result<(), ReustmannError> {
+        self.check_address(addr)?;
+        self.sp = addr;
+        Ok(())
+    }
+
+    /// Force the `nz` flag, it stays as is until an instruction updates it.
+    pub fn set_nz(&mut self, flag: bool) {
+        self.nz = flag;
+    }
+
+    fn check_address(&self, address: usize) -> Result<(), ReustmannError> {
+        let arch_length = self.memory.len();
+        if address < arch_length { Ok(()) }
+        else { Err(ReustmannError::AddressOutOfBounds { address, arch_length }) }
+    }
+
+    #[inline]
+    fn reset_registers(&mut self) -> Statement {
         self.pc = 0;
         self.sp = 0;
         self.nz = false;
@@ -102,10 +707,31 @@ impl Interpreter {
     }
 
     #[inline]
-    fn set_nz(&mut self, val: u8) {
+    fn update_nz(&mut self, val: u8) {
         self.nz = val != 0;
     }
 
+    #[inline]
+    fn write_cell(&mut self, addr: usize, val: u8) {
+        if !self.protected.is_empty() && self.is_protected(addr) {
+            self.write_blocked = true;
+            return
+        }
+        if let Some(ref mut history) = self.history {
+            history.record_write(addr, self.memory[addr]);
+        }
+        self.memory[addr] = val;
+        self.mark_dirty(addr);
+    }
+
+    #[inline]
+    fn mark_dirty(&mut self, addr: usize) {
+        if !self.is_dirty[addr] {
+            self.is_dirty[addr] = true;
+            self.dirty.push(addr);
+        }
+    }
+
     #[inline]
     fn decrement_sp(&mut self) {
         self.sp = if self.sp == 0 { self.memory.len() - 1 } else { self.sp - 1 };
@@ -123,36 +749,55 @@ impl Interpreter {
     }
 
     // FIXME use Bytes iterator ?
-    fn execute<R: ?Sized + Read, W: ?Sized + Write>(&mut self, op: OpCode, input: &mut R, output: &mut W) -> Statement {
+    fn execute<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, op: OpCode, input: &mut R, output: &mut W) -> Statement {
         match op {
-            RESET => self.reset(),
+            RESET => self.reset_registers(),
             HALT => Statement(op, true),
             IN => {
-                let mut status = true;
+                let (val, status) = match input.read_byte() {
+                    Ok(None) => match self.eof {
+                        EofBehavior::PushZero => (0, true),
+                        EofBehavior::PushValue(val) => (val, true),
+                        EofBehavior::Halt => return Statement(HALT, true),
+                        EofBehavior::NoChange => {
+                            self.failure = Some(FailureReason::InputExhausted);
+                            self.increment_pc();
+                            return Statement(op, false)
+                        },
+                    },
+                    Ok(Some(val)) => (val, true),
+                    Err(_) => {
+                        self.failure = Some(FailureReason::InputError);
+                        (0, false) // FIXME save/return error ???
+                    },
+                };
                 self.decrement_sp();
-                let mut buffer = [0; 1];
-                if let Err(_) = input.read(&mut buffer) { // FIXME save/return error ???
-                    status = false;
-                }
-                self.memory[self.sp] = buffer[0];
-                self.set_nz(buffer[0]);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, status)
             },
             OUT => {
+                if self.output_limit_reached() {
+                    self.failure = Some(FailureReason::OutputLimitReached);
+                    self.increment_pc();
+                    return Statement(op, false)
+                }
                 let mut status = true;
                 let val = self.memory[self.sp];
-                if let Err(_) = output.write(&[val]) { // FIXME save/return error ???
+                if let Err(_) = output.write_byte(val) { // FIXME save/return error ???
+                    self.failure = Some(FailureReason::OutputError);
                     status = false;
                 }
-                self.set_nz(val);
+                self.output_count += 1;
+                self.update_nz(val);
                 self.increment_sp();
                 self.increment_pc();
                 Statement(op, status)
             },
             POP => {
                 let val = self.memory[self.sp];
-                self.set_nz(val);
+                self.update_nz(val);
                 self.increment_sp();
                 self.increment_pc();
                 Statement(op, true)
@@ -160,8 +805,8 @@ impl Interpreter {
             DUP => {
                 let tmp = self.memory[self.sp];
                 self.decrement_sp();
-                self.memory[self.sp] = tmp;
-                self.set_nz(tmp);
+                self.write_cell(self.sp, tmp);
+                self.update_nz(tmp);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -169,8 +814,8 @@ impl Interpreter {
                 // let val = self.trunc(self.pc);
                 let val = self.pc as u8; // FIXME use trunc
                 self.decrement_sp();
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -200,24 +845,24 @@ impl Interpreter {
             PUSHNZ => {
                 let val = self.nz as u8;
                 self.decrement_sp();
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
             SWAP => {
                 let tmp = self.memory[self.sp];
                 let arch_len = self.memory.len();
-                self.memory[self.sp] = self.memory[(self.sp + 1) % arch_len];
-                self.memory[(self.sp + 1) % arch_len] = tmp;
+                self.write_cell(self.sp, self.memory[(self.sp + 1) % arch_len]);
+                self.write_cell((self.sp + 1) % arch_len, tmp);
                 self.increment_pc();
                 Statement(op, true)
             },
             PUSH0 => {
                 self.decrement_sp();
                 let val = 0;
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -226,8 +871,8 @@ impl Interpreter {
                 let a = self.memory[(self.sp + 2) % self.memory.len()];
                 let b = self.memory[(self.sp + 1) % self.memory.len()];
                 let val = a.wrapping_add(b);
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -236,22 +881,22 @@ impl Interpreter {
                 let a = self.memory[(self.sp + 2) % self.memory.len()];
                 let b = self.memory[(self.sp + 1) % self.memory.len()];
                 let val = a.wrapping_sub(b);
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
             INC => {
                 let val = self.memory[self.sp].wrapping_add(1);
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
             DEC => {
                 let val = self.memory[self.sp].wrapping_sub(1);
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -260,8 +905,8 @@ impl Interpreter {
                 let a = self.memory[(self.sp + 2) % self.memory.len()];
                 let b = self.memory[(self.sp + 1) % self.memory.len()];
                 let val = a.wrapping_mul(b);
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -270,8 +915,8 @@ impl Interpreter {
                 let a = self.memory[(self.sp + 2) % self.memory.len()];
                 let b = self.memory[(self.sp + 1) % self.memory.len()];
                 let val = if b != 0 { a.wrapping_div(b) } else { u8::max_value() };
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -280,8 +925,8 @@ impl Interpreter {
                 let a = self.memory[(self.sp + 2) % self.memory.len()];
                 let b = self.memory[(self.sp + 1) % self.memory.len()];
                 let val = a ^ b;
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -290,8 +935,8 @@ impl Interpreter {
                 let a = self.memory[(self.sp + 2) % self.memory.len()];
                 let b = self.memory[(self.sp + 1) % self.memory.len()];
                 let val = a & b;
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -300,29 +945,29 @@ impl Interpreter {
                 let a = self.memory[(self.sp + 2) % self.memory.len()];
                 let b = self.memory[(self.sp + 1) % self.memory.len()];
                 let val = a | b;
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
             SHL => {
                 let val = self.memory[self.sp] << 1;
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
             SHR => {
                 let val = self.memory[self.sp] >> 1;
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
             NOT => {
                 let val = !self.memory[self.sp];
-                self.memory[self.sp] = val;
-                self.set_nz(val);
+                self.write_cell(self.sp, val);
+                self.update_nz(val);
                 self.increment_pc();
                 Statement(op, true)
             },
@@ -402,7 +1047,7 @@ impl Interpreter {
                 if self.pc < self.memory.len() - 1 {
                     for i in self.pc + 1..self.memory.len() {
                         if self.memory[i] == TARGET {
-                            self.sp = i;
+                            self.pc = (i + 1) % self.memory.len();
                             found = true;
                             break;
                         }
@@ -475,12 +1120,307 @@ impl Interpreter {
         }
     }
 
+    /// Execute the consecutive `NOP`s starting at `pc`, at most one pass over the memory,
+    /// return the number of `NOP`s executed.
+    fn execute_nops(&mut self) -> usize {
+        let entry = self.history.as_ref().map(|_| self.history_entry());
+        self.failure = None;
+        let len = self.memory.len();
+        let mut executed = 0;
+        while executed < len {
+            let op = self.current_opcode();
+            if op != NOP && is_valid_op_code(op) { break }
+            if executed > 0 && self.is_breakpoint(self.pc) { break }
+            self.total_cost += self.costs.as_ref().map_or(1, |costs| costs[op as usize] as u64);
+            self.instruction_count += 1;
+            self.increment_pc();
+            executed += 1;
+        }
+        if let (Some(history), Some(entry)) = (self.history.as_mut(), entry) {
+            if executed > 0 { history.push(entry) }
+        }
+        executed
+    }
+
     /// Use [Empty](https://doc.rust-lang.org/std/io/struct.Empty.html) and/or
     /// [Sink](https://doc.rust-lang.org/std/io/struct.Sink.html)
     /// if you don't want to give input and/or output.
-    pub fn step<R: ?Sized + Read, W: ?Sized + Write>(&mut self, input: &mut R, output: &mut W) -> Statement {
-        let instr = self.memory[self.pc];
-        self.execute(instr, input, output)
+    ///
+    /// Each `OUT` writes a single byte to `output`, wrap unbuffered outputs like a `File`
+    /// in a [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+    /// and flush it once the run is over.
+    pub fn step<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, input: &mut R, output: &mut W) -> Statement {
+        let instr = self.current_opcode();
+        if self.history.is_some() {
+            self.push_history_entry();
+        }
+        self.total_cost += self.costs.as_ref().map_or(1, |costs| costs[instr as usize] as u64);
+        self.instruction_count += 1;
+        self.failure = None;
+        let statement = self.execute(instr, input, output);
+        if self.write_blocked {
+            self.write_blocked = false;
+            self.failure = Some(FailureReason::WriteProtected);
+            return Statement(statement.0, false)
+        }
+        statement
+    }
+
+    /// Return the raw op code at `pc`, the one the next step will execute.
+    #[inline]
+    pub fn current_opcode(&self) -> OpCode {
+        self.cell_at_pc()
+    }
+
+    /// Read the cell at `pc` without a bounds check.
+    ///
+    /// Every write of `pc` is either checked against the memory length or wrapped modulo it,
+    /// and the memory is never resized (`reset_with_program` keeps the arch length,
+    /// `restore` rejects snapshots of another length), so `pc` always indexes the memory.
+    #[inline]
+    fn cell_at_pc(&self) -> OpCode {
+        debug_assert!(self.pc < self.memory.len(), "pc {} out of the memory", self.pc);
+        unsafe { *self.memory.get_unchecked(self.pc) }
+    }
+
+    /// The `pc`, the raw op code at `pc` and its instruction, without executing it.
+    pub fn peek(&self) -> (usize, OpCode, Instruction) {
+        let op_code = self.current_opcode();
+        (self.pc, op_code, Instruction::from(op_code))
+    }
+
+    /// The address the instruction at `pc` jumps to when it is taken, in the current state.
+    ///
+    /// Conditional branches skip the next instruction when taken,
+    /// `POPPC` jumps to the address on top of the stack.
+    /// `None` if the instruction doesn't jump, if `ENDL`, `BRAN` or `BRAP`
+    /// find no `LOOP` or `TARGET` to jump to, or if `pc` is outside the memory.
+    pub fn branch_target(&self, pc: usize) -> Option<usize> {
+        let len = self.memory.len();
+        let op = *self.memory.get(pc)?;
+        match op {
+            RESET => Some(0),
+            POPPC => Some((self.memory[self.sp] as usize) % len),
+            BZ | BNZ | BEQ | BGT | BLT | BGE => Some((pc + 2) % len),
+            ENDL => self.memory[..pc].iter().rposition(|&op| op == LOOP).map(|i| (i + 1) % len),
+            BRAN => self.memory[pc + 1..].iter().position(|&op| op == TARGET).map(|i| (pc + 1 + i + 1) % len),
+            BRAP => self.memory[..pc].iter().rposition(|&op| op == TARGET).map(|i| (i + 1) % len),
+            SKIP1..=SKIP9 => Some((pc + (op - SKIP1) as usize + 2) % len),
+            _ => None,
+        }
+    }
+
+    /// Execute exactly `n` instructions, even if a `HALT` is encountered,
+    /// return the number of instructions executed and the last statement.
+    pub fn step_n<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, n: usize, input: &mut R, output: &mut W)
+        -> (usize, Option<Statement>) {
+
+        let mut statement = None;
+        for _ in 0..n {
+            statement = Some(self.step(input, output));
+        }
+        (n, statement)
+    }
+
+    /// Execute at most `n` instructions and stop after a `HALT`,
+    /// return the statement of each instruction executed, the `HALT` one included.
+    pub fn step_many<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, n: usize, input: &mut R, output: &mut W)
+        -> Vec<Statement> {
+
+        let mut statements = Vec::new();
+        for _ in 0..n {
+            let statement = self.step(input, output);
+            statements.push(statement);
+            if let Statement(HALT, _) = statement { break }
+        }
+        statements
+    }
+
+    /// Execute instructions until an `OUT` writes a byte, a `HALT` is encountered
+    /// or `max_steps` instructions are executed, return the number of instructions
+    /// executed, the last statement and the byte written if any.
+    pub fn run_until_output<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, max_steps: usize, input: &mut R, output: &mut W)
+        -> (usize, Option<Statement>, Option<u8>) {
+
+        let mut statement = None;
+        for executed in 1..=max_steps {
+            let val = self.memory[self.sp];
+            let stat = self.step(input, output);
+            statement = Some(stat);
+            match stat {
+                Statement(OUT, true) => return (executed, statement, Some(val)),
+                Statement(HALT, _) => return (executed, statement, None),
+                _ => (),
+            }
+        }
+        (max_steps, statement, None)
+    }
+
+    /// Execute instructions until the `nz` flag changes, a `HALT` is encountered
+    /// or `max_steps` instructions are executed, return the number of instructions
+    /// executed and the last statement.
+    pub fn run_until_nz_change<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, max_steps: usize, input: &mut R, output: &mut W)
+        -> (usize, Option<Statement>) {
+
+        let nz = self.nz;
+        self.run_until(max_steps, input, output, |interpreter| interpreter.nz != nz)
+    }
+
+    /// Execute instructions until the next one to execute is `instruction`,
+    /// a `HALT` is encountered or `max_steps` instructions are executed,
+    /// return the number of instructions executed and the last statement.
+    ///
+    /// At least one instruction is executed, so calling it again will stop
+    /// before the next occurrence of `instruction`.
+    pub fn run_until_instruction<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, instruction: Instruction, max_steps: usize, input: &mut R, output: &mut W)
+        -> (usize, Option<Statement>) {
+
+        let op_code: OpCode = instruction.into();
+        self.run_until(max_steps, input, output, |interpreter| {
+            OpCode::from(Instruction::from(interpreter.current_opcode())) == op_code
+        })
+    }
+
+    /// Execute instructions until `pc` reaches `addr`, a `HALT` is encountered
+    /// or `max_steps` instructions are executed, return the number of instructions
+    /// executed and the last statement.
+    ///
+    /// At least one instruction is executed, even if `pc` is already at `addr`.
+    pub fn run_until_pc<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, addr: usize, max_steps: usize, input: &mut R, output: &mut W)
+        -> (usize, Option<Statement>) {
+
+        self.run_until(max_steps, input, output, |interpreter| interpreter.pc == addr)
+    }
+
+    /// Execute instructions until a `HALT` is encountered or `max_steps` instructions
+    /// are executed, `hook` sees the state after each instruction with its statement.
+    /// Return the number of instructions executed and the last statement.
+    ///
+    /// The hook gets the `DebugInfos` of each step, the memory is copied every time.
+    pub fn run_with_hook<R, W, F>(&mut self, max_steps: usize, input: &mut R, output: &mut W, mut hook: F)
+        -> (usize, Option<Statement>)
+        where R: ?Sized + ByteIn, W: ?Sized + ByteOut, F: FnMut(&DebugInfos, &Statement) {
+
+        let mut statement = None;
+        for executed in 1..=max_steps {
+            let stat = self.step(input, output);
+            statement = Some(stat);
+            hook(&self.debug_infos(), &stat);
+            if let Statement(HALT, _) = stat {
+                return (executed, statement)
+            }
+        }
+        (max_steps, statement)
+    }
+
+    /// Execute instructions until `stop` returns `true` after a step,
+    /// a `HALT` is encountered or `max_steps` instructions are executed.
+    fn run_until<R, W, F>(&mut self, max_steps: usize, input: &mut R, output: &mut W, mut stop: F)
+        -> (usize, Option<Statement>)
+        where R: ?Sized + ByteIn, W: ?Sized + ByteOut, F: FnMut(&Interpreter) -> bool {
+
+        let mut statement = None;
+        for executed in 1..=max_steps {
+            let stat = self.step(input, output);
+            statement = Some(stat);
+            if let Statement(HALT, _) = stat {
+                return (executed, statement)
+            }
+            if stop(self) {
+                return (executed, statement)
+            }
+        }
+        (max_steps, statement)
+    }
+
+    /// Execute instructions until a `HALT` is encountered, a step fails, the output limit
+    /// is reached or `max_steps` instructions are executed, for programs that may never halt.
+    ///
+    /// Breakpoints are ignored, the steps of the outcome are the number of instructions executed.
+    pub fn run_with_limit<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, max_steps: usize, input: &mut R, output: &mut W)
+        -> RunOutcome {
+
+        for executed in 1..=max_steps {
+            match self.step(input, output) {
+                Statement(HALT, _) => return RunOutcome::Halted(executed),
+                statement @ Statement(_, false) => return RunOutcome::Failed(statement),
+                Statement(OUT, true) if self.output_limit_reached() => return RunOutcome::OutputLimitReached(executed),
+                _ => (),
+            }
+        }
+        RunOutcome::LimitReached(max_steps)
+    }
+
+    /// Execute instructions until a `HALT` is encountered, a step fails,
+    /// the output limit is reached or `pc` is at a breakpoint,
+    /// return the number of instructions executed and the last statement.
+    ///
+    /// Breakpoints are checked before executing an instruction but the first one,
+    /// calling `run` again resumes from a breakpoint.
+    ///
+    /// Nothing is allocated while running, unlike `debug_infos`
+    /// which copies the whole memory and is best called once the run is over.
+    ///
+    /// Be careful, a program that never halts will run forever.
+    pub fn run<R: ?Sized + ByteIn, W: ?Sized + ByteOut>(&mut self, input: &mut R, output: &mut W)
+        -> (usize, Option<Statement>) {
+
+        self.run_with_control(input, output, |_, _| ControlFlow::Continue(()))
+    }
+
+    /// Same as `run` but `hook` is called after each instruction with its statement,
+    /// the run stops after the current instruction when it returns `ControlFlow::Break`.
+    pub fn run_with_control<R, W, F>(&mut self, input: &mut R, output: &mut W, mut hook: F)
+        -> (usize, Option<Statement>)
+        where R: ?Sized + ByteIn, W: ?Sized + ByteOut, F: FnMut(&Interpreter, Statement) -> ControlFlow<()> {
+
+        let mut executed = 0;
+        let mut last_statement = None;
+        loop {
+            // the first instruction is executed even if it is a breakpoint, to resume from it
+            if executed > 0 && self.is_breakpoint(self.pc) {
+                return (executed, last_statement)
+            }
+            if self.fold_nops {
+                let nops = self.execute_nops();
+                if nops > 0 {
+                    executed += nops;
+                    let statement = Statement(NOP, true);
+                    last_statement = Some(statement);
+                    if let ControlFlow::Break(()) = hook(self, statement) {
+                        return (executed, Some(statement))
+                    }
+                    continue
+                }
+            }
+            let statement = self.step(input, output);
+            last_statement = Some(statement);
+            executed += 1;
+            match statement {
+                Statement(HALT, _) | Statement(_, false) => return (executed, Some(statement)),
+                _ if self.output_limit_reached() => return (executed, Some(statement)),
+                _ => (),
+            }
+            if let ControlFlow::Break(()) = hook(self, statement) {
+                return (executed, Some(statement))
+            }
+        }
+    }
+
+    /// Step lazily, the iterator yields each executed instruction
+    /// with the byte it wrote and ends after a `HALT`.
+    ///
+    /// Be careful, the iterator of a program that never halts never ends.
+    pub fn events<'a, R: ?Sized + ByteIn>(&'a mut self, input: &'a mut R) -> impl Iterator<Item=StepEvent> + 'a {
+        let mut output = Vec::with_capacity(1);
+        let mut halted = false;
+        core::iter::from_fn(move || {
+            if halted { return None }
+            let pc = self.pc;
+            let statement = self.step(input, &mut output);
+            halted = statement.0 == HALT;
+            Some(StepEvent { pc, statement, output: output.pop() })
+        })
     }
 
     /// Get a debug struct that can help for debugging programs
@@ -489,7 +1429,422 @@ impl Interpreter {
             memory: OpCodes(self.memory.clone()),
             pc: self.pc,
             sp: self.sp,
+            nz: self.nz,
+            steps: self.instruction_count
+        }
+    }
+
+    /// Save the memory and the registers, the options and the counters are not part of it.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            arch_width: self.arch_width,
+            memory: self.memory.clone(),
+            pc: self.pc,
+            sp: self.sp,
+            nz: self.nz
+        }
+    }
+
+    /// Go back to the memory and the registers of `snapshot`, nothing is allocated.
+    ///
+    /// The snapshot must come from an interpreter of the same arch length and width,
+    /// like `reset` the restored cells are not tracked by `take_dirty`.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), ReustmannError> {
+        if snapshot.memory.len() != self.memory.len() || snapshot.arch_width != self.arch_width {
+            return Err(ReustmannError::SnapshotMismatch {
+                snapshot_length: snapshot.memory.len(),
+                snapshot_width: snapshot.arch_width as usize,
+                arch_length: self.memory.len(),
+                arch_width: self.arch_width as usize
+            });
+        }
+        self.clear_history();
+        self.memory.copy_from_slice(&snapshot.memory);
+        self.pc = snapshot.pc;
+        self.sp = snapshot.sp;
+        self.nz = snapshot.nz;
+        Ok(())
+    }
+
+    /// Get the whole state of the interpreter,
+    /// `Interpreter::from_state` gives it back.
+    pub fn state(&self) -> InterpreterState {
+        InterpreterState {
+            arch_width: self.arch_width,
+            memory: self.memory.clone(),
+            image: self.image.clone(),
+            eof: self.eof,
+            output_limit: self.output_limit,
+            output_count: self.output_count,
+            costs: self.costs.clone(),
+            total_cost: self.total_cost,
+            instruction_count: self.instruction_count,
+            fold_nops: self.fold_nops,
+            pc: self.pc,
+            sp: self.sp,
             nz: self.nz
         }
     }
+
+    /// Same as `debug_infos` but borrows the memory instead of copying it.
+    pub fn debug_view(&self) -> DebugView<'_> {
+        DebugView {
+            memory: &self.memory,
+            pc: self.pc,
+            sp: self.sp,
+            nz: self.nz,
+            steps: self.instruction_count
+        }
+    }
+
+    /// Write the whole memory to `out` in the given format.
+    #[cfg(feature = "std")]
+    pub fn dump_memory<W: ?Sized + io::Write>(&self, out: &mut W, format: DumpFormat) -> io::Result<()> {
+        if let DumpFormat::Raw = format {
+            return out.write_all(&self.memory)
+        }
+        for (line, cells) in self.memory.chunks(DUMP_LINE_CELLS).enumerate() {
+            write!(out, "{:08x}:", line * DUMP_LINE_CELLS)?;
+            for op_code in cells {
+                write!(out, " {:02x}", op_code)?;
+            }
+            if let DumpFormat::HexAscii = format {
+                // pad the last line to keep the gutter aligned
+                for _ in cells.len()..DUMP_LINE_CELLS {
+                    write!(out, "   ")?;
+                }
+                let ascii: String = cells.iter().map(|&c| if (32..=126).contains(&c) { c as char } else { '.' }).collect();
+                write!(out, "  |{}|", ascii)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io::empty;
+
+    use super::*;
+
+    fn interpreter_with(mnemos: &[u8], arch_length: usize) -> Interpreter {
+        let mut interpreter = Interpreter::new(arch_length, 8).unwrap();
+        interpreter.copy_program_bytes(mnemos).unwrap();
+        interpreter
+    }
+
+    #[test]
+    fn run_with_limit_stops_at_the_output_limit() {
+        // push and write a byte forever
+        let program = Program::from_iter(b"L0.O]".iter().cloned());
+        let mut interpreter = InterpreterBuilder::new(16, 8).output_limit(Some(3)).build().unwrap();
+        interpreter.copy_program(&program).unwrap();
+
+        let mut output = Vec::new();
+        match interpreter.run_with_limit(100, &mut empty(), &mut output) {
+            RunOutcome::OutputLimitReached(steps) => assert_eq!(steps, 12),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+        assert_eq!(output, [1, 1, 1]);
+    }
+
+    #[test]
+    fn run_with_hook_sees_the_state_after_each_step() {
+        let mut interpreter = interpreter_with(b"00+H", 8);
+        let mut seen = Vec::new();
+        let (executed, _) = interpreter.run_with_hook(10, &mut empty(), &mut Vec::new(), |infos, &Statement(op_code, ok)| {
+            seen.push((infos.pc, infos.sp, infos.steps, op_code, ok));
+        });
+        assert_eq!(executed, 4);
+        assert_eq!(seen, [(1, 7, 1, PUSH0, true), (2, 6, 2, PUSH0, true), (3, 5, 3, ADD, true), (3, 5, 4, HALT, true)]);
+    }
+
+    #[test]
+    fn reset_restores_a_self_modified_cell() {
+        // the push wraps the stack to the last cell, overwriting the `HALT`
+        let mut interpreter = interpreter_with(b"0;;H", 4);
+        interpreter.step(&mut empty(), &mut Vec::new());
+        assert_eq!(interpreter.debug_view().memory[3], 0);
+
+        interpreter.reset();
+        assert_eq!(interpreter.debug_view().memory, [PUSH0, NOP, NOP, HALT]);
+        assert_eq!((interpreter.debug_view().pc, interpreter.debug_view().sp), (0, 0));
+    }
+
+    #[test]
+    fn eof_value_modes() {
+        let mut interpreter = InterpreterBuilder::new(4, 8).eof_value(Some(0xff)).build().unwrap();
+        interpreter.copy_program_bytes(b"I").unwrap();
+        let Statement(op_code, ok) = interpreter.step(&mut empty(), &mut Vec::new());
+        assert_eq!((op_code, ok), (IN, true));
+        let view = interpreter.debug_view();
+        assert_eq!((view.sp, view.memory[view.sp], view.nz), (3, 0xff, true));
+
+        let mut interpreter = InterpreterBuilder::new(4, 8).eof_value(None).build().unwrap();
+        interpreter.copy_program_bytes(b"I").unwrap();
+        let Statement(op_code, ok) = interpreter.step(&mut empty(), &mut Vec::new());
+        assert_eq!((op_code, ok), (IN, false));
+        assert_eq!(interpreter.failure_reason(), Some(FailureReason::InputExhausted));
+        let view = interpreter.debug_view();
+        assert_eq!((view.pc, view.sp, view.memory[3], view.nz), (1, 0, NOP, false));
+    }
+
+    #[test]
+    fn binary_ops_read_the_memory_below_an_empty_stack() {
+        type BinaryOp = fn(u8, u8) -> u8;
+        let binary_ops: [(u8, BinaryOp); 7] = [
+            (b'+', u8::wrapping_add),
+            (b'-', u8::wrapping_sub),
+            (b'*', u8::wrapping_mul),
+            (b'/', |a, b| a.checked_div(b).unwrap_or(u8::MAX)),
+            (b'^', |a, b| a ^ b),
+            (b'&', |a, b| a & b),
+            (b'|', |a, b| a | b),
+        ];
+        for &(mnemo, op) in &binary_ops {
+            // no operand: the words at 0 and 1, the op itself and `Dup`
+            let mut interpreter = interpreter_with(&[mnemo, b'D'], 4);
+            let Statement(op_code, ok) = interpreter.step(&mut empty(), &mut Vec::new());
+            assert!(ok && interpreter.failure_reason().is_none(), "{}", mnemo as char);
+            let view = interpreter.debug_view();
+            assert_eq!((view.sp, view.memory[3]), (3, op(DUP, op_code)), "{}", mnemo as char);
+
+            // one operand: the pushed `0` and the `Push0` at 0
+            let mut interpreter = interpreter_with(&[b'0', mnemo], 4);
+            interpreter.step_n(2, &mut empty(), &mut Vec::new());
+            assert!(interpreter.failure_reason().is_none(), "{}", mnemo as char);
+            let view = interpreter.debug_view();
+            assert_eq!((view.sp, view.memory[2]), (2, op(PUSH0, 0)), "{}", mnemo as char);
+        }
+    }
+
+    #[test]
+    fn clone_does_not_alias_the_memory() {
+        let mut interpreter = interpreter_with(b"00.H", 8);
+        interpreter.step(&mut empty(), &mut Vec::new());
+        let before = interpreter.debug_infos();
+
+        let mut clone = interpreter.clone();
+        clone.step_n(2, &mut empty(), &mut Vec::new());
+        assert_ne!(clone.debug_infos().memory.0, before.memory.0);
+
+        let after = interpreter.debug_infos();
+        assert_eq!(after.memory.0, before.memory.0);
+        assert_eq!((after.pc, after.sp, after.nz, after.steps), (before.pc, before.sp, before.nz, before.steps));
+    }
+
+    #[test]
+    fn run_stops_before_a_breakpoint() {
+        let mut interpreter = interpreter_with(b"0.0.H", 8);
+        interpreter.add_breakpoint(3).unwrap();
+        let (executed, _) = interpreter.run(&mut empty(), &mut Vec::new());
+        assert_eq!((executed, interpreter.debug_view().pc), (3, 3));
+
+        assert!(interpreter.remove_breakpoint(3));
+        let (executed, statement) = interpreter.run(&mut empty(), &mut Vec::new());
+        assert_eq!(executed, 2);
+        assert!(matches!(statement, Some(Statement(HALT, true))));
+    }
+
+    #[test]
+    fn run_with_limit_reaches_the_limit_of_an_infinite_loop() {
+        let mut interpreter = interpreter_with(b"L]", 2);
+        match interpreter.run_with_limit(1000, &mut empty(), &mut Vec::new()) {
+            RunOutcome::LimitReached(steps) => assert_eq!(steps, 1000),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn step_many_stops_after_the_halt() {
+        let mut interpreter = interpreter_with(b"00H;", 8);
+        let statements = interpreter.step_many(10, &mut empty(), &mut Vec::new());
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements.len() as u64, interpreter.instruction_count());
+        assert!(matches!(statements.last(), Some(&Statement(HALT, true))));
+
+        let mut interpreter = interpreter_with(b"L]", 2);
+        assert_eq!(interpreter.step_many(5, &mut empty(), &mut Vec::new()).len(), 5);
+    }
+
+    #[test]
+    fn writing_a_protected_cell_fails() {
+        // `Dup` copies its own op code to the last cell
+        let mut interpreter = interpreter_with(b"DH", 8);
+        interpreter.protect_range(7, 1).unwrap();
+        let Statement(op_code, ok) = interpreter.step(&mut empty(), &mut Vec::new());
+        assert_eq!((op_code, ok), (DUP, false));
+        assert_eq!(interpreter.failure_reason(), Some(FailureReason::WriteProtected));
+        assert_eq!(interpreter.debug_view().memory[7], NOP);
+
+        let mut interpreter = interpreter_with(b"DH", 8);
+        interpreter.protect_range(6, 1).unwrap();
+        let Statement(_, ok) = interpreter.step(&mut empty(), &mut Vec::new());
+        assert!(ok);
+        assert_eq!(interpreter.debug_view().memory[7], DUP);
+
+        interpreter.reset();
+        interpreter.protect_range(7, 1).unwrap();
+        interpreter.unprotect_all();
+        assert!(interpreter.step(&mut empty(), &mut Vec::new()).1);
+    }
+
+    #[test]
+    fn eof_behaviors_against_an_empty_input() {
+        let run_in = |eof| {
+            // the `Dup` in the last cell shows if something was pushed
+            let mut interpreter = interpreter_with(b"I;;D", 4);
+            interpreter.set_eof_behavior(eof);
+            let Statement(op_code, ok) = interpreter.step(&mut empty(), &mut Vec::new());
+            let view = interpreter.debug_view();
+            (op_code, ok, view.pc, view.sp, view.memory[3])
+        };
+        assert_eq!(run_in(EofBehavior::PushZero), (IN, true, 1, 3, 0));
+        assert_eq!(run_in(EofBehavior::PushValue(0xff)), (IN, true, 1, 3, 0xff));
+        assert_eq!(run_in(EofBehavior::Halt), (HALT, true, 0, 0, DUP));
+        assert_eq!(run_in(EofBehavior::NoChange), (IN, false, 1, 0, DUP));
+    }
+
+    #[test]
+    fn peek_reports_the_instruction_step_executes() {
+        let mut interpreter = interpreter_with(b"0.O", 8);
+        for _ in 0..3 {
+            let (pc, op_code, instruction) = interpreter.peek();
+            assert_eq!(interpreter.peek(), (pc, op_code, instruction));
+            assert_eq!(pc, interpreter.debug_view().pc);
+            let Statement(executed, _) = interpreter.step(&mut empty(), &mut Vec::new());
+            assert_eq!(executed, op_code);
+            assert_eq!(Instruction::from(executed), instruction);
+        }
+    }
+
+    #[test]
+    fn reset_with_program_keeps_the_arch_length() {
+        let mut interpreter = interpreter_with(b"0.O", 16);
+        interpreter.step_n(3, &mut empty(), &mut Vec::new());
+
+        // on a memory of 2 cells the push would overwrite the `HALT`
+        interpreter.reset_with_program(&Program::from_iter(b"0H".iter().cloned())).unwrap();
+        assert_eq!(interpreter.arch_length(), 16);
+        assert_eq!(interpreter.instruction_count(), 0);
+        assert!(interpreter.take_dirty().is_empty());
+        match interpreter.run_with_limit(100, &mut empty(), &mut Vec::new()) {
+            RunOutcome::Halted(steps) => assert_eq!(steps, 2),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+
+        let too_large = Program::from_iter(vec![b';'; 17]);
+        assert_eq!(interpreter.reset_with_program(&too_large),
+                   Err(ReustmannError::ProgramTooLarge { program_length: 17, arch_length: 16 }));
+    }
+
+    #[test]
+    fn bran_jumps_after_the_next_target() {
+        let mut interpreter = interpreter_with(b"B0T.H", 8);
+        let sp = interpreter.debug_view().sp;
+        interpreter.step(&mut empty(), &mut Vec::new());
+        assert_eq!((interpreter.debug_view().pc, interpreter.debug_view().sp), (3, sp));
+        assert_eq!(interpreter.branch_target(0), Some(3));
+
+        // without a following `TARGET` it is a `NOP`, the search doesn't wrap
+        let mut interpreter = interpreter_with(b"TB", 2);
+        interpreter.set_pc(1).unwrap();
+        interpreter.step(&mut empty(), &mut Vec::new());
+        assert_eq!(interpreter.debug_view().pc, 0);
+        assert_eq!(interpreter.debug_view().sp, 0);
+    }
+
+    #[test]
+    fn set_registers_survive_a_nop() {
+        let mut interpreter = interpreter_with(b"", 8);
+        interpreter.set_pc(5).unwrap();
+        assert_eq!(interpreter.debug_infos().pc, 5);
+        interpreter.set_sp(3).unwrap();
+        assert_eq!(interpreter.debug_infos().sp, 3);
+        interpreter.set_nz(true);
+        assert!(interpreter.debug_infos().nz);
+
+        interpreter.step(&mut empty(), &mut Vec::new());
+        let infos = interpreter.debug_infos();
+        assert_eq!((infos.pc, infos.sp, infos.nz), (6, 3, true));
+
+        assert!(interpreter.set_pc(8).is_err());
+        assert!(interpreter.set_sp(8).is_err());
+        assert_eq!((interpreter.debug_infos().pc, interpreter.debug_infos().sp), (6, 3));
+    }
+
+    #[test]
+    fn memory_accessors_at_the_boundary() {
+        let mut interpreter = interpreter_with(b"", 8);
+        assert_eq!(interpreter.get_memory(7), Some(NOP));
+        interpreter.set_memory(7, 42).unwrap();
+        assert_eq!(interpreter.get_memory(7), Some(42));
+
+        assert_eq!(interpreter.get_memory(8), None);
+        assert_eq!(interpreter.set_memory(8, 42), Err(ReustmannError::AddressOutOfBounds { address: 8, arch_length: 8 }));
+        assert_eq!(interpreter.get_memory(0), Some(NOP));
+    }
+    #[test]
+    fn step_back_restores_the_initial_state() {
+        // push, increment, duplicate, add, write the sum and restart
+        let mut interpreter = interpreter_with(b"0.D+O0.Dz", 16);
+        let initial = (interpreter.debug_infos(), interpreter.output_count);
+        assert_eq!(interpreter.step_back(), Err(HistoryError::Disabled));
+
+        interpreter.enable_history(16);
+        assert_eq!(interpreter.step_back(), Err(HistoryError::Empty));
+
+        let mut output = Vec::new();
+        for _ in 0..12 {
+            interpreter.step(&mut empty(), &mut output);
+        }
+        assert_eq!(output, [2]);
+        assert_ne!(interpreter.debug_infos().memory.0, initial.0.memory.0);
+        for _ in 0..12 {
+            interpreter.step_back().unwrap();
+        }
+        assert_eq!(interpreter.step_back(), Err(HistoryError::Empty));
+
+        let infos = interpreter.debug_infos();
+        assert_eq!((infos.pc, infos.sp, infos.nz), (initial.0.pc, initial.0.sp, initial.0.nz));
+        assert_eq!(infos.memory.0, initial.0.memory.0);
+        assert_eq!(interpreter.output_count, initial.1);
+    }
+    #[test]
+    fn with_memory_rejects_empty_memories_and_invalid_widths() {
+        let interpreter = Interpreter::with_memory(vec![HALT, NOP, NOP], 8).unwrap();
+        let infos = interpreter.debug_infos();
+        assert_eq!(infos.memory.0, [HALT, NOP, NOP]);
+        assert_eq!((infos.pc, infos.sp, infos.nz), (0, 0, false));
+
+        assert_eq!(Interpreter::with_memory(Vec::new(), 8).err(), Some(InterpreterError::EmptyMemory));
+        assert_eq!(Interpreter::with_memory(vec![NOP], 0).err(), Some(InterpreterError::InvalidArchWidth { arch_width: 0 }));
+        assert_eq!(Interpreter::with_memory(vec![NOP], 33).err(), Some(InterpreterError::InvalidArchWidth { arch_width: 33 }));
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trips_through_json() {
+        let mut interpreter = Interpreter::new(16, 8).unwrap();
+        interpreter.copy_program_bytes(b"0.D+O0.").unwrap();
+        let mut output = Vec::new();
+        for _ in 0..5 {
+            interpreter.step(&mut &b""[..], &mut output);
+        }
+        assert_eq!(output, [2]);
+
+        let json = serde_json::to_string(&interpreter.state()).unwrap();
+        let state: InterpreterState = serde_json::from_str(&json).unwrap();
+        let restored = Interpreter::from_state(state).unwrap();
+
+        let (before, after) = (interpreter.debug_infos(), restored.debug_infos());
+        assert_eq!(after.memory.0, before.memory.0);
+        assert_eq!((after.pc, after.sp, after.nz, after.steps), (before.pc, before.sp, before.nz, before.steps));
+        assert_eq!(restored.state(), interpreter.state());
+    }
 }
@@ -140,6 +140,11 @@
 //! will be written to the top of memory at location **L**-1, and the new **SP**
 //! will contain the value **L**-1 and will grow downward from there.
 //!
+//! The stack has no depth: an instruction always reads its operands at **SP**,
+//! **SP** + 1, ... modulo **L**, whatever was pushed before. An `ADD` executed
+//! right after reset therefore adds the words at locations 0 and 1,
+//! there is no stack underflow.
+//!
 //! ## NZ – Non-Zero Flag
 //! The non-zero flag **NZ** reflects the non-zeroness of the most recent instruction
 //! that read or wrote memory, as defined in the details below. **NZ** = true
@@ -163,9 +168,30 @@
 
 // FIXME rename me Iota Machine ?!?!
 // but this already exist !!!
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+extern crate core;
+extern crate alloc;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+
+mod assembler;
+mod byte_io;
+mod error;
+mod history;
 mod program;
 mod interpreter;
+mod lockstep;
+#[cfg(feature = "std")]
+mod fuzz;
+#[cfg(feature = "parallel")]
+mod batch;
 
+#[cfg(feature = "std")]
+pub mod analysis;
 pub mod instruction;
 pub mod memory;
 
@@ -173,5 +199,16 @@ pub mod memory;
 // pub use instruction::op_codes::OpCode;
 // pub use instruction::{Mnemonic, LongMnemonic};
 
-pub use program::Program;
-pub use interpreter::{Interpreter, Statement, DebugInfos};
+pub use assembler::{AssembleError, SourceMap};
+pub use byte_io::{ByteIn, ByteOut, ByteIoError};
+#[cfg(feature = "mmap")]
+pub use byte_io::MappedInput;
+pub use error::{InterpreterError, ReustmannError};
+pub use history::HistoryError;
+pub use program::{Program, ProgramReport};
+pub use interpreter::{Interpreter, InterpreterBuilder, Statement, StepEvent, RunOutcome, EofBehavior, FailureReason, DumpFormat, DebugInfos, DebugView, InterpreterState, Snapshot};
+pub use lockstep::lockstep;
+#[cfg(feature = "std")]
+pub use fuzz::fuzz_one;
+#[cfg(feature = "parallel")]
+pub use batch::run_batch;
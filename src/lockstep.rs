@@ -0,0 +1,35 @@
+use alloc::vec::Vec;
+
+use crate::instruction::op_codes::HALT;
+use crate::interpreter::{Interpreter, Statement};
+
+/// `true` if the registers and the memory of both interpreters are the same.
+fn same_state(a: &Interpreter, b: &Interpreter) -> bool {
+    let (a, b) = (a.debug_view(), b.debug_view());
+    a.pc == b.pc && a.sp == b.sp && a.nz == b.nz && a.memory == b.memory
+}
+
+/// Step both interpreters with the same `input` and compare them after each step,
+/// until a `HALT` or `max_steps` instructions, for differential testing.
+///
+/// Return the number of the first step after which the statements, the bytes written,
+/// the registers or the memory differ, `Some(0)` if they already differ before
+/// the first step, `None` if they stayed identical.
+pub fn lockstep(a: &mut Interpreter, b: &mut Interpreter, input: &[u8], max_steps: usize) -> Option<usize> {
+    if !same_state(a, b) {
+        return Some(0)
+    }
+    let (mut input_a, mut input_b) = (input, input);
+    let (mut output_a, mut output_b) = (Vec::new(), Vec::new());
+    for step in 1..=max_steps {
+        let Statement(op_a, success_a) = a.step(&mut input_a, &mut output_a);
+        let Statement(op_b, success_b) = b.step(&mut input_b, &mut output_b);
+        if op_a != op_b || success_a != success_b || output_a != output_b || !same_state(a, b) {
+            return Some(step)
+        }
+        if op_a == HALT { break }
+        output_a.clear();
+        output_b.clear();
+    }
+    None
+}
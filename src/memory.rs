@@ -1,4 +1,6 @@
-use std::ops::Deref;
+use core::ops::Deref;
+
+use alloc::vec::Vec;
 
 use crate::instruction::{Instruction, Mnemonic, LongMnemonic};
 use crate::instruction::op_codes::OpCode;
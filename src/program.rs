@@ -1,25 +1,387 @@
+#[cfg(feature = "std")]
 use std::{fs, io};
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
 use std::path::Path;
 
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::assembler::{self, AssembleError, SourceMap};
+#[cfg(feature = "std")]
+use crate::error::ReustmannError;
+use crate::instruction::{Instruction, LongMnemonic, is_valid_mnemonic, is_valid_op_code};
+use crate::instruction::op_codes::OpCode;
+use crate::memory::OpCodes;
+
+/// Convert a source byte to the op code stored in the interpreter memory.
+pub(crate) fn op_code_of(mnemo: u8) -> OpCode {
+    if is_valid_mnemonic(mnemo as char) {
+        Into::<Instruction>::into(mnemo as char).into()
+    } else { mnemo }
+}
+
+/// The bytes of a program, read in memory or mapped from a file.
+enum Source {
+    Owned(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+/// The diagnostics of `Program::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramReport {
+    /// The number of bytes of the program.
+    pub length: usize,
+    /// The addresses of the cells that are not instructions once copied
+    /// in the interpreter memory, executed as `NOP`s.
+    pub invalid_op_codes: Vec<usize>,
+    /// The addresses of the `HALT`s.
+    pub halts: Vec<usize>,
+    /// Whether the program is longer than the arch length it was validated against.
+    pub too_large: bool,
+}
+
+impl ProgramReport {
+    /// Without a `HALT` the program can only be stopped from the outside.
+    pub fn has_halt(&self) -> bool {
+        !self.halts.is_empty()
+    }
+}
+
 /// A set of instructions that can be given to an interpreter.
-pub struct Program(Vec<u8>);
+pub struct Program(Source);
 
 impl Program {
     /// Construct a new Program from a source.
     ///
     /// Make sure that you truncate the final newline if any.
+    #[cfg(feature = "std")]
     pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Program> {
         fs::read(path).map(Self::from_iter)
     }
 
+    /// Construct a program backed by a read-only memory map of the file,
+    /// the bytes are neither read nor copied until the program is used.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or another one,
+    /// while the program is alive: the bytes of the program would change under it
+    /// and reading a truncated part is undefined behavior.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn from_mmap<P: AsRef<Path>>(path: P) -> io::Result<Program> {
+        let file = fs::File::open(path)?;
+        let map = memmap2::Mmap::map(&file)?;
+        Ok(Program(Source::Mapped(map)))
+    }
+
     /// Construct a program from a list of instructions (mnemonic).
     pub fn from_iter<I: IntoIterator<Item=u8>>(iter: I) -> Program {
-        Program(iter.into_iter().collect())
+        Program(Source::Owned(iter.into_iter().collect()))
+    }
+
+    /// Construct a program from the bytes of a source, without any of its `\n`
+    /// if `ignore_nl` is `true`, the same program as `new` reading these bytes.
+    pub fn from_bytes(bytes: &[u8], ignore_nl: bool) -> Program {
+        Program::from_iter(bytes.iter().cloned().filter(|&byte| !ignore_nl || byte != b'\n'))
+    }
+
+    /// Read a program like `new` does, failing with the `ReustmannError::ProgramTooLarge`
+    /// message if it is longer than `max_len`.
+    ///
+    /// The whole input is read so the message holds the true program length.
+    #[cfg(feature = "std")]
+    pub fn new_with_max<R: Read>(reader: R, ignore_nl: bool, max_len: usize) -> Result<Program, String> {
+        let program = Program::new(reader, ignore_nl).map_err(|err| err.to_string())?;
+        if program.memory().len() > max_len {
+            let err = ReustmannError::ProgramTooLarge { program_length: program.memory().len(), arch_length: max_len };
+            return Err(err.to_string())
+        }
+        Ok(program)
+    }
+
+    /// Read a program, without any of its `\n` if `ignore_nl` is `true`,
+    /// it is `new_filtered` with a filter keeping the bytes that are not `\n`.
+    ///
+    /// A `\r` is not stripped, use `new_filtered` to load a source with CRLF newlines.
+    #[cfg(feature = "std")]
+    pub fn new<R: Read>(reader: R, ignore_nl: bool) -> io::Result<Program> {
+        Program::new_filtered(reader, |byte| !ignore_nl || byte != b'\n')
+    }
+
+    /// Read a program made of the bytes for which `filter` returns `true`.
+    ///
+    /// The `ignore_nl` of `new` only strips the `\n`s, filter out `\r` too
+    /// to load a source with CRLF newlines:
+    /// `Program::new_filtered(reader, |b| b != b'\r' && b != b'\n')`.
+    #[cfg(feature = "std")]
+    pub fn new_filtered<R: Read, F: Fn(u8) -> bool>(mut reader: R, filter: F) -> io::Result<Program> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        bytes.retain(|&byte| filter(byte));
+        Ok(Program::from_iter(bytes))
+    }
+
+    /// Assemble a program from whitespace separated short or long mnemonics,
+    /// one instruction per cell.
+    ///
+    /// Long mnemonics are case insensitive, a `;` starts a comment up to the end
+    /// of the line so `Nop` must be written with its long mnemonic.
+    ///
+    /// A `name:` defines a label at the address of the next instruction, an `@name`
+    /// assembles to the 17 instructions that push the address of the label,
+    /// to jump there with `PopPc`. Labels can be used before they are defined
+    /// but their address must fit in a cell, below 256.
+    pub fn from_source(text: &str) -> Result<Program, AssembleError> {
+        Program::from_source_with_map(text).map(|(program, _)| program)
+    }
+
+    /// Assemble a source like `from_source` and return the source line of each cell,
+    /// to show the line of `pc` while debugging, the map doesn't change the program.
+    pub fn from_source_with_map(text: &str) -> Result<(Program, SourceMap), AssembleError> {
+        assembler::assemble(text).map(|(mnemos, map)| (Program::from_iter(mnemos), map))
+    }
+
+    /// Construct a program from an annotated source, only the bytes before
+    /// the first whitespace of each line are kept.
+    ///
+    /// A line like `+  add the two top values` gives `+`, newlines are not
+    /// part of the program.
+    pub fn from_opcode_prefixes(source: &[u8]) -> Program {
+        let prefixes = source.split(|&b| b == b'\n')
+                             .flat_map(|line| line.iter().take_while(|b| !b.is_ascii_whitespace()));
+        Program::from_iter(prefixes.cloned())
     }
 
     /// Get the u8 representation of the source.
     pub fn memory(&self) -> &[u8] {
-        &self.0
+        match self.0 {
+            Source::Owned(ref bytes) => bytes,
+            #[cfg(feature = "mmap")]
+            Source::Mapped(ref map) => map,
+        }
+    }
+
+    /// Copy the bytes of the source, the ones the program was constructed with.
+    ///
+    /// Writing them to a file and loading it back gives the same program,
+    /// the newlines dropped by `ignore_nl` are not restored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.memory().to_vec()
+    }
+
+    /// Report the invalid op codes and the `HALT`s of the program
+    /// and if it fits in an interpreter of `arch_length`.
+    pub fn validate(&self, arch_length: usize) -> ProgramReport {
+        let memory = self.memory();
+        // the bytes that are not mnemonics are copied as raw op codes, some of them valid
+        let addresses_of = |predicate: fn(OpCode) -> bool| {
+            memory.iter().enumerate()
+                  .filter(|&(_, &mnemo)| predicate(op_code_of(mnemo)))
+                  .map(|(addr, _)| addr)
+                  .collect()
+        };
+        ProgramReport {
+            length: memory.len(),
+            invalid_op_codes: addresses_of(|op_code| !is_valid_op_code(op_code)),
+            halts: addresses_of(|op_code| Instruction::from(op_code) == Instruction::Halt),
+            too_large: memory.len() > arch_length,
+        }
+    }
+
+    /// The address and the op code of every cell that is not an instruction
+    /// once copied in the interpreter memory, empty if the program is fully valid.
+    pub fn invalid_op_codes(&self) -> Vec<(usize, OpCode)> {
+        self.memory().iter().enumerate()
+            .map(|(addr, &mnemo)| (addr, op_code_of(mnemo)))
+            .filter(|&(_, op_code)| !is_valid_op_code(op_code))
+            .collect()
+    }
+
+    /// The smallest arch width that lets a word hold the address of every cell,
+    /// the smallest `W` with `2^W >= memory().len()`, kept in the `[6..32]` range
+    /// accepted by the interpreter.
+    pub fn suggested_arch_width(&self) -> usize {
+        let length = self.memory().len();
+        let width = (usize::BITS - length.saturating_sub(1).leading_zeros()) as usize;
+        width.clamp(6, 32)
+    }
+
+    /// The CRC-32 of the source bytes, the one of zlib and PNG
+    /// (reflected polynomial `0xEDB88320`, initial value and final xor `0xFFFFFFFF`).
+    ///
+    /// It is computed on the bytes as given, the final newline included.
+    pub fn checksum(&self) -> u32 {
+        let crc = self.memory().iter().fold(!0u32, |crc, &byte| {
+            (0..8).fold(crc ^ byte as u32, |crc, _| {
+                if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 }
+            })
+        });
+        !crc
+    }
+
+    /// The address, the op code and the long mnemonic of every cell of the program.
+    ///
+    /// The cells that are not instructions keep their raw byte as op code and,
+    /// like the debugger shows them, the quoted char of that byte, `'x'`,
+    /// in place of a long mnemonic so they stay distinct from a real `Nop`.
+    pub fn disassemble(&self) -> Vec<(usize, OpCode, String)> {
+        self.memory().iter().enumerate().map(|(addr, &mnemo)| {
+            let op_code = op_code_of(mnemo);
+            let name = if is_valid_op_code(op_code) {
+                LongMnemonic::from(Instruction::from(op_code)).to_string()
+            } else {
+                format!("'{}'", op_code as char)
+            };
+            (addr, op_code, name)
+        }).collect()
+    }
+
+    /// Append the bytes of `other` after the bytes of the program,
+    /// a mapped program is copied in memory first.
+    ///
+    /// Nothing is relocated, see `concat`.
+    pub fn append(&mut self, other: &Program) {
+        match self.0 {
+            Source::Owned(ref mut bytes) => bytes.extend_from_slice(other.memory()),
+            #[cfg(feature = "mmap")]
+            Source::Mapped(_) => *self = Program::concat([&*self, other]),
+        }
+    }
+
+    /// The bytes of all the `parts` one after the other, from a slice of programs
+    /// or references like `Program::concat([&a, &b])`, each part starts
+    /// at the base offset of the total length of the previous ones.
+    ///
+    /// Nothing is relocated: absolute addresses pushed and popped by a part are now
+    /// off by its base offset, and branches searching for a `TARGET` or a `LOOP`
+    /// can find one in another part, for example a `BRAN` of `a` without
+    /// a following `TARGET` will now jump to the first `TARGET` of `b`.
+    pub fn concat<'a, I: IntoIterator<Item = &'a Program>>(parts: I) -> Program {
+        Program::from_iter(parts.into_iter().flat_map(|part| part.memory()).cloned())
+    }
+
+    /// The address, the byte of `self` and the byte of `other` of every cell that differs,
+    /// sorted by address, the programs must have the same length.
+    pub fn diff(&self, other: &Program) -> Result<Vec<(usize, u8, u8)>, &'static str> {
+        let (a, b) = (self.memory(), other.memory());
+        if a.len() != b.len() {
+            return Err("Programs of different lengths can't be compared cell by cell");
+        }
+        Ok(a.iter().zip(b).enumerate()
+            .filter(|&(_, (old, new))| old != new)
+            .map(|(addr, (&old, &new))| (addr, old, new))
+            .collect())
+    }
+
+    /// Get the op codes of the source, as they will be copied in the interpreter memory.
+    pub fn op_codes(&self) -> OpCodes {
+        OpCodes(self.memory().iter().map(|&mnemo| op_code_of(mnemo)).collect())
+    }
+}
+
+
+/// Programs are equal when they have the same bytes,
+/// whether they are mapped or in memory.
+impl PartialEq for Program {
+    fn eq(&self, other: &Program) -> bool {
+        self.memory() == other.memory()
     }
 }
 
+impl Eq for Program {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_checks_the_copied_op_codes() {
+        // `0x02` is not a mnemonic but is copied as the `HALT` op code
+        let report = Program::from_iter(vec![b'0', 0x02, b'O']).validate(3);
+        assert_eq!(report.halts, vec![1]);
+        assert!(report.invalid_op_codes.is_empty());
+
+        // a newline runs as `SpTgt`, `0xff` as `Nop`
+        let report = Program::from_iter(vec![b'\n', 0xff, b'H']).validate(2);
+        assert_eq!(report.invalid_op_codes, vec![1]);
+        assert_eq!(report.halts, vec![2]);
+        assert!(report.too_large);
+    }
+
+    #[test]
+    fn new_filters_newlines() {
+        let source: &[u8] = b"0O\r\n]\n";
+        assert_eq!(Program::new(source, true).unwrap().memory(), b"0O\r]");
+        assert_eq!(Program::new(source, false).unwrap().memory(), source);
+        let program = Program::new_filtered(source, |b| b != b'\r' && b != b'\n').unwrap();
+        assert_eq!(program.memory(), b"0O]");
+    }
+
+    #[test]
+    fn new_with_max_rejects_one_byte_over_the_limit() {
+        let source: &[u8] = b"0.\nO\n";
+        assert_eq!(Program::new_with_max(source, true, 3).unwrap().memory(), b"0.O");
+
+        let err = Program::new_with_max(source, true, 2).err().unwrap();
+        let expected = ReustmannError::ProgramTooLarge { program_length: 3, arch_length: 2 };
+        assert_eq!(err, expected.to_string());
+
+        assert!(Program::new_with_max(source, false, 5).is_ok());
+        let err = Program::new_with_max(source, false, 4).err().unwrap();
+        let expected = ReustmannError::ProgramTooLarge { program_length: 5, arch_length: 4 };
+        assert_eq!(err, expected.to_string());
+    }
+
+    #[test]
+    fn new_with_max_reports_the_true_length() {
+        let source: &[u8] = b"0.O.0.O.";
+        let err = Program::new_with_max(source, true, 2).err().unwrap();
+        let expected = ReustmannError::ProgramTooLarge { program_length: 8, arch_length: 2 };
+        assert_eq!(err, expected.to_string());
+    }
+
+    #[test]
+    fn from_bytes_matches_new() {
+        for &source in &[&b"0.O]\n"[..], b"0\n.\nO", b"\n", b""] {
+            for &ignore_nl in &[true, false] {
+                let program = Program::from_bytes(source, ignore_nl);
+                assert_eq!(program.memory(), Program::new(source, ignore_nl).unwrap().memory());
+            }
+        }
+        assert_eq!(Program::from_bytes(b"0\n.\nO\n", true).memory(), b"0.O");
+    }
+
+    #[test]
+    fn suggested_arch_width_of_lengths() {
+        let width = |length| Program::from_iter(vec![b';'; length]).suggested_arch_width();
+        // below 64 cells the minimum width of the interpreter is suggested
+        assert_eq!(width(1), 6);
+        assert_eq!(width(65), 7);
+        assert_eq!(width(255), 8);
+        assert_eq!(width(256), 8);
+        assert_eq!(width(257), 9);
+    }
+
+    #[test]
+    fn equality_and_diff() {
+        let original = Program::from_iter(b"0.O]".iter().cloned());
+        let mutated = Program::from_iter(b"0,OH".iter().cloned());
+        assert!(original == Program::from_bytes(b"0.O]\n", true));
+        assert!(original != mutated);
+        assert_eq!(original.diff(&mutated).unwrap(), vec![(1, b'.', b','), (3, b']', b'H')]);
+        assert!(original.diff(&original).unwrap().is_empty());
+        assert!(original.diff(&Program::from_iter(b"0.".iter().cloned())).is_err());
+    }
+
+    #[test]
+    fn disassemble_a_small_program() {
+        let listing = Program::from_iter(b"0x;H".iter().cloned()).disassemble();
+        let listing: Vec<_> = listing.iter().map(|&(addr, op_code, ref name)| (addr, op_code, name.as_str())).collect();
+        // the invalid byte keeps its value and reads differently from the `Nop`
+        assert_eq!(listing, [(0, 13, "Push0"), (1, b'x', "'x'"), (2, 0, "Nop"), (3, 2, "Halt")]);
+    }
+}